@@ -0,0 +1,190 @@
+// Runs the simulation without a window, e.g. on servers or in CI
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use game_of_life::formats::{self, Format, Pattern};
+use game_of_life::options::{self, any, parse_value, Error, GameOptions};
+use game_of_life::{hashlife, rules, Board, Game, Shape};
+
+const USAGE_HEAD: &str = "\
+Game of life without a window
+
+USAGE:
+    headless [OPTIONS]
+
+OPTIONS:
+";
+
+const USAGE_TAIL: &str = "\
+    -g, --generations <COUNT>   Number of generations to run [default: 100]
+        --until-stable          Stop early once the board repeats itself
+        --hashlife              Jump by powers of two generations with hashlife, needs
+                                a life-like rule and the plane topology, the board
+                                shows its area of the unbounded universe
+    -o, --output <FILE>         Write the resulting board (.rle, .cells, .lif)
+        --print                 Print the resulting board to the standard output
+    -h, --help                  Print this help
+";
+
+struct Options {
+    game: GameOptions,
+    generations: u64,
+    until_stable: bool,
+    hashlife: bool,
+    output: Option<PathBuf>,
+    print: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            game: GameOptions::default(),
+            generations: 100,
+            until_stable: false,
+            hashlife: false,
+            output: None,
+            print: false,
+        }
+    }
+}
+
+fn parse_options(args: impl IntoIterator<Item = String>) -> Result<Options, Error> {
+    let mut options = Options::default();
+    options::parse_args(args, |option, value| {
+        match option {
+            "-g" | "--generations" => options.generations = parse_value(option, value()?, any)?,
+            "--until-stable" => options.until_stable = true,
+            "--hashlife" => options.hashlife = true,
+            "-o" | "--output" => options.output = Some(value()?.into()),
+            "--print" => options.print = true,
+            _ => options.game.parse_option(option, value)?,
+        }
+        Ok(())
+    })?;
+    Ok(options)
+}
+
+fn create_game(options: &Options, seed: u64) -> Result<Game<'static>, Box<dyn std::error::Error>> {
+    let mut game = options.game.create_game(seed)?;
+    if options.hashlife {
+        game.enable_hashlife()
+            .ok_or("Hashlife needs a life-like rule without B0 and the plane topology")?;
+    }
+    Ok(game)
+}
+
+fn board_hash(board: &Board) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    board.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Runs the game, returns generation at which the board started repeating
+// and the period
+fn run(
+    game: &mut Game,
+    options: &Options,
+    seed: u64,
+) -> Result<Option<(u64, u64)>, Box<dyn std::error::Error>> {
+    if !options.until_stable {
+        for _ in 0..options.generations {
+            game.step();
+        }
+        return Ok(None);
+    }
+    // Only hashes of the boards are kept, a matching hash is confirmed by
+    // running the game again up to the earlier board, as it could be
+    // a collision
+    let mut seen: HashMap<u64, u64> = HashMap::new();
+    for i in 0..=options.generations {
+        if i > 0 {
+            game.step();
+        }
+        let hash = board_hash(&game.board);
+        if let Some(&generation) = seen.get(&hash) {
+            let mut earlier = create_game(options, seed)?;
+            for _ in 0..generation {
+                earlier.step();
+            }
+            if earlier.board == game.board {
+                return Ok(Some((generation, game.generation - generation)));
+            }
+        }
+        seen.insert(hash, game.generation);
+    }
+    Ok(None)
+}
+
+// Advances by `generations` in as few hashlife jumps as possible, fails
+// once the universe can't grow any further
+fn jump(game: &mut Game, generations: u64) -> Option<()> {
+    let mut remaining = generations;
+    while remaining > 0 {
+        let step = (u64::BITS - 1 - remaining.leading_zeros()).min(hashlife::MAX_STEP.into());
+        game.step_pow2(step as u8)?;
+        remaining -= 1 << step;
+    }
+    Some(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(Error::Help) => {
+            print!("{}{}{}", USAGE_HEAD, options::USAGE, USAGE_TAIL);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!(
+                "Error: {}\n\nRun with --help to see the available options",
+                e
+            );
+            std::process::exit(2);
+        }
+    };
+
+    let seed = options.game.seed.unwrap_or_else(rand::random);
+    if options.game.pattern.is_none() {
+        println!("seed: {}", seed);
+    }
+    let mut game = create_game(&options, seed)?;
+    let start = std::time::Instant::now();
+    // Repeats can be found only generation by generation
+    let stable = if options.hashlife && !options.until_stable {
+        jump(&mut game, options.generations).ok_or("Pattern has spread too far for hashlife")?;
+        None
+    } else {
+        run(&mut game, &options, seed)?
+    };
+    let elapsed = start.elapsed();
+
+    let shape = Shape::from_board(&game.board);
+    println!("generation: {}", game.generation);
+    println!("population: {}", game.board.population());
+    let (width, height) = shape.dimensions();
+    println!(
+        "bounding box: {}x{} at ({}, {})",
+        width,
+        height,
+        shape.pos().0,
+        shape.pos().1
+    );
+    if let Some((generation, period)) = stable {
+        println!("stable: period {} since generation {}", period, generation);
+    }
+    println!("time: {:?}", elapsed);
+
+    // Dying cells of multi-state rules are written too
+    let mut pattern = Pattern::from_board(&game.board);
+    pattern.name = Some(format!("Generation {}", game.generation));
+    pattern.rule = Some(std::mem::replace(&mut game.rule, Box::new(rules::Empty {})));
+    if options.print {
+        print!("{}", Format::Plaintext.write(&pattern));
+    }
+    if let Some(path) = &options.output {
+        formats::write_file(path, &pattern)?;
+    }
+    Ok(())
+}
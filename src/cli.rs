@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+
+use game_of_life::game_of_life_io::{Palette, ResizePolicy, Speed};
+use game_of_life::options::{self, any, parse_value, positive, Error, GameOptions};
+
+const USAGE_HEAD: &str = "\
+Game of life
+
+USAGE:
+    game_of_life [OPTIONS]
+
+OPTIONS:
+";
+
+const USAGE_TAIL: &str = "\
+    -c, --cell-size <PIXELS>    Size of a cell on the screen [default: 8]
+        --fps <FPS>             Frames per second, 1-255 [default: 60]
+        --resize <POLICY>       What happens when the window is resized: rescale-cells,
+                                resize-board or letterbox [default: rescale-cells]
+        --speed <GENS/FRAMES>   Generations per frames, e.g. 1/5 or 16/1 [default: 1/5]
+        --ticks <FRAMES>        Frames between generations, same as --speed 1/FRAMES
+        --history <COUNT>       Number of past states kept for undo, 0 disables it
+                                [default: 1000]
+        --history-memory <MIB>  Memory limit of the undo history [default: 256]
+        --paused                Start with the simulation paused
+        --no-hud                Start with the text overlay hidden
+        --palette <NAME>        Color cells by their age: plain, heat or rainbow
+                                [default: plain]
+        --trail                 Dead cells fade out instead of disappearing
+        --load <FILE>           Load game saved by F5, board options are ignored
+    -h, --help                  Print this help
+
+KEYS:
+    Space, P                   Pause/resume the simulation
+    N                          Step one generation, pauses the simulation
+    +, -                       Double/halve the speed
+    0-9, G                     Type generation number and run to it, Backspace
+                               deletes the last digit
+    X                          Fill the board with a new random seed
+    Shift+X                    Fill the board again with the last seed
+    S                          Place soup with a new random seed at the mouse
+    Shift+S                    Place soup with the last seed at the mouse
+    D                          Cycle symmetry of the soup
+    C                          Clear the board
+    Q, W, E, R                 Place still life, oscillator, ship or other shape
+                               at the mouse
+    Left click                 Toggle cell, or set it to the drawing state
+    [, ]                       Previous/next drawing state of multi-state rules
+    Mouse wheel                Zoom in/out around the cursor
+    Right/middle drag, arrows  Pan the view
+    F                          Zoom to fit the pattern
+    Shift+F                    Reset the view
+    H                          Show/hide the text overlay
+    A                          Cycle color palette of cell ages
+    T                          Toggle trails of dead cells
+    M                          Cycle what happens when the window is resized
+    Ctrl+Z, Ctrl+Y             Undo/redo generation or edit, undo pauses the simulation
+    Comma, Period              Hold to scrub backward/forward through the history
+    Home, End                  Jump to the oldest/newest state in the history
+    F5, F9                     Save/load the game
+    Escape                     Quit
+";
+
+pub fn usage() -> String {
+    format!("{}{}{}", USAGE_HEAD, options::USAGE, USAGE_TAIL)
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub game: GameOptions,
+    pub cell_size: u32,
+    pub fps: u8,
+    pub resize_policy: ResizePolicy,
+    pub speed: Speed,
+    pub history: usize,
+    pub history_memory: usize,
+    pub paused: bool,
+    pub no_hud: bool,
+    pub palette: Palette,
+    pub trail: bool,
+    pub load: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            game: GameOptions::default(),
+            cell_size: 8,
+            fps: 60,
+            resize_policy: ResizePolicy::default(),
+            speed: Speed::default(),
+            history: 1000,
+            history_memory: 256,
+            paused: false,
+            no_hud: false,
+            palette: Palette::default(),
+            trail: false,
+            load: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, Error> {
+        let mut options = Options::default();
+        options::parse_args(args, |option, value| {
+            match option {
+                "-c" | "--cell-size" => {
+                    options.cell_size = parse_value(option, value()?, positive)?
+                }
+                "--fps" => options.fps = parse_value(option, value()?, positive)?,
+                "--resize" => options.resize_policy = parse_value(option, value()?, any)?,
+                "--speed" => options.speed = parse_value(option, value()?, any)?,
+                "--ticks" => {
+                    let frames = parse_value(option, value()?, positive)?;
+                    options.speed = Speed::FramesPerGeneration(frames);
+                }
+                "--history" => options.history = parse_value(option, value()?, any)?,
+                "--history-memory" => options.history_memory = parse_value(option, value()?, any)?,
+                "--paused" => options.paused = true,
+                "--no-hud" => options.no_hud = true,
+                "--palette" => options.palette = parse_value(option, value()?, any)?,
+                "--trail" => options.trail = true,
+                "--load" => options.load = Some(value()?.into()),
+                _ => options.game.parse_option(option, value)?,
+            }
+            Ok(())
+        })?;
+        Ok(options)
+    }
+}
@@ -6,9 +6,49 @@ pub use shapes::Shape;
 
 pub mod rules;
 
+pub mod topology;
+pub use topology::Topology;
+
+pub mod formats;
+
+pub mod sparse_board;
+pub use sparse_board::SparseBoard;
+
+pub mod hashlife;
+pub use hashlife::HashLife;
+
+pub mod snapshot;
+
+pub mod soup;
+pub use soup::Symmetry;
+
+pub mod history;
+pub use history::History;
+
+pub mod ages;
+pub use ages::CellAges;
+
+pub mod thread_pool;
+pub use thread_pool::ThreadPool;
+
 pub struct Game<'a> {
     pub board: Board,
     pub rule: Box<dyn 'a + rules::Rule>,
+    // Number of generations stepped since the game was created
+    pub generation: u64,
+    hashlife: Option<HashLifeEngine>,
+    history: Option<History>,
+    ages: Option<CellAges>,
+    // Only used with more than one thread
+    pool: Option<ThreadPool>,
+}
+
+// Unbounded universe of which the board shows the area from (0, 0)
+#[derive(Debug, Clone)]
+struct HashLifeEngine {
+    universe: HashLife,
+    // Board as it was after the last step, to detect user changes
+    synced: Board,
 }
 
 impl<'a> Game<'a> {
@@ -16,9 +56,18 @@ impl<'a> Game<'a> {
     where
         R: 'a + rules::Rule,
     {
+        Game::create_boxed(width, height, Box::new(rule))
+    }
+
+    pub fn create_boxed(width: usize, height: usize, rule: Box<dyn 'a + rules::Rule>) -> Self {
         Self {
             board: Board::create(width, height),
-            rule: Box::new(rule),
+            rule,
+            generation: 0,
+            hashlife: None,
+            history: None,
+            ages: None,
+            pool: None,
         }
     }
 
@@ -30,21 +79,353 @@ impl<'a> Game<'a> {
         game.randomize();
         game
     }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.board.set_topology(topology);
+        self
+    }
+
+    // Number of threads used to compute each generation, the result
+    // doesn't depend on it
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.set_threads(threads);
+        self
+    }
 }
 
 impl Game<'_> {
+    pub fn threads(&self) -> usize {
+        self.pool.as_ref().map_or(1, ThreadPool::threads)
+    }
+
+    // Starts a new pool of worker threads, which stay alive until the number
+    // of threads changes or the game is dropped
+    pub fn set_threads(&mut self, threads: usize) {
+        if threads != self.threads() {
+            self.pool = if threads > 1 {
+                Some(ThreadPool::create(threads))
+            } else {
+                None
+            };
+        }
+    }
+
     pub fn clear(&mut self) {
-        self.board.clear()
+        self.board.clear();
+        self.edit_ages();
+    }
+
+    // Changes size of the board keeping the pattern in the centre, resizes
+    // follow the window, so they are not recorded in the history
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.board = self.board.resized(width, height);
+        self.edit_ages();
+        if self.hashlife.is_some() {
+            self.enable_hashlife();
+        }
     }
 
     pub fn randomize(&mut self) {
-        self.board.randomize()
+        self.board.randomize();
+        self.edit_ages();
+    }
+
+    pub fn randomize_seeded(&mut self, seed: u64, density: f64) {
+        self.board.randomize_seeded(seed, density);
+        self.edit_ages();
+    }
+
+    pub fn randomize_rect_seeded(
+        &mut self,
+        seed: u64,
+        density: f64,
+        pos: (i32, i32),
+        size: (usize, usize),
+    ) -> Option<()> {
+        let result = self.board.randomize_rect_seeded(seed, density, pos, size);
+        self.edit_ages();
+        result
+    }
+
+    pub fn add_soup(
+        &mut self,
+        seed: u64,
+        density: f64,
+        symmetry: Symmetry,
+        pos: (i32, i32),
+        size: usize,
+    ) -> Option<()> {
+        let result = self.board.add_soup(seed, density, symmetry, pos, size);
+        self.edit_ages();
+        result
     }
 }
 
 impl Game<'_> {
     pub fn step(&mut self) -> Board {
-        let new_board = self.rule.next_board(&self.board);
-        std::mem::replace(&mut self.board, new_board)
+        if self.hashlife.is_some() {
+            if let Some(old_board) = self.step_pow2(0) {
+                return old_board;
+            }
+        }
+        let new_board = match &self.pool {
+            Some(pool) => self.rule.next_board_parallel(&self.board, pool),
+            None => self.rule.next_board(&self.board),
+        };
+        let generation = self.generation;
+        self.generation = self.generation.saturating_add(1);
+        let old_board = std::mem::replace(&mut self.board, new_board);
+        self.record(&old_board, generation);
+        self.update_ages();
+        old_board
+    }
+
+    // Advances by 2^step generations using hashlife, returns the board from
+    // before the jump, fails if hashlife is not enabled or was dropped as it
+    // can't run the current rule or topology, if `step` is larger than
+    // `hashlife::MAX_STEP` or if the universe can't grow any further
+    pub fn step_pow2(&mut self, step: u8) -> Option<Board> {
+        if step > hashlife::MAX_STEP || !self.sync_hashlife() {
+            return None;
+        }
+        let engine = self.hashlife.as_mut().expect("Internal logic error");
+        engine.universe.step_pow2(step)?;
+        let mut new_board = self.board.clone();
+        engine.universe.write_board(&mut new_board, (0, 0));
+        engine.synced = new_board.clone();
+        let generation = self.generation;
+        self.generation = self.generation.saturating_add(1u64 << step);
+        let old_board = std::mem::replace(&mut self.board, new_board);
+        self.record(&old_board, generation);
+        // The jump counts as a single generation
+        self.update_ages();
+        Some(old_board)
+    }
+
+    // Steps `generations` times, at least once, and returns the board from
+    // before the first step, which is the only state recorded in the history
+    pub fn step_batch(&mut self, generations: u64) -> Board {
+        let history = self.history.take();
+        let generation = self.generation;
+        let old_board = self.step();
+        for _ in 1..generations {
+            self.step();
+        }
+        self.history = history;
+        self.record(&old_board, generation);
+        old_board
+    }
+
+    // Pushes changes made to the board since the last step into the universe,
+    // returns false when hashlife is not in use or can't be used any more
+    fn sync_hashlife(&mut self) -> bool {
+        let mut engine = match self.hashlife.take() {
+            Some(engine) => engine,
+            None => return false,
+        };
+        if self.board.topology() != Topology::Plane {
+            return false;
+        }
+        let rule = self.rule.life_like();
+        if rule.as_ref() != Some(engine.universe.rule()) {
+            match rule.and_then(|rule| HashLife::from_board(rule, &self.board)) {
+                Some(universe) => engine.universe = universe,
+                None => return false,
+            }
+        } else if engine.synced != self.board {
+            let changed = self
+                .board
+                .indexed_iter()
+                .zip(engine.synced.indexed_iter())
+                .filter(|(new, old)| new.0 != old.0);
+            for ((&cell, (x, y)), _) in changed {
+                if engine.universe.set(x.into(), y.into(), cell).is_none() {
+                    return false;
+                }
+            }
+        }
+        engine.synced = self.board.clone();
+        self.hashlife = Some(engine);
+        true
+    }
+}
+
+impl Game<'_> {
+    // Switches to hashlife, in which case the board is a view of an unbounded
+    // universe, fails for rules which are not life-like and for boards with
+    // other topology than the plane
+    pub fn enable_hashlife(&mut self) -> Option<()> {
+        let universe = HashLife::from_board(self.rule.life_like()?, &self.board)?;
+        self.hashlife = Some(HashLifeEngine {
+            universe,
+            synced: self.board.clone(),
+        });
+        Some(())
+    }
+
+    pub fn disable_hashlife(&mut self) {
+        self.hashlife = None;
+    }
+
+    pub fn hashlife(&self) -> Option<&HashLife> {
+        self.hashlife.as_ref().map(|engine| &engine.universe)
+    }
+
+    pub fn hashlife_mut(&mut self) -> Option<&mut HashLife> {
+        self.hashlife.as_mut().map(|engine| &mut engine.universe)
+    }
+}
+
+impl Game<'_> {
+    // Past states are recorded on every step or batch of steps and by `edit`
+    pub fn set_history(&mut self, history: Option<History>) {
+        self.history = history;
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn history_mut(&mut self) -> Option<&mut History> {
+        self.history.as_mut()
+    }
+
+    fn record(&mut self, board: &Board, generation: u64) {
+        if let Some(history) = &mut self.history {
+            history.push(history::State {
+                board: board.clone(),
+                generation,
+            });
+        }
+    }
+
+    // Applies `f` to the board, recording the previous state if the board
+    // was changed, so that the edit can be undone, changed cells start
+    // counting their age from 0
+    pub fn edit<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut Board) -> T,
+    {
+        let result = if self.history.is_none() {
+            f(&mut self.board)
+        } else {
+            let old_board = self.board.clone();
+            let result = f(&mut self.board);
+            if self.board != old_board {
+                self.record(&old_board, self.generation);
+            }
+            result
+        };
+        self.edit_ages();
+        result
+    }
+
+    // Returns to the previous state in the history
+    pub fn undo(&mut self) -> Option<()> {
+        self.travel(History::undo)
+    }
+
+    // Returns to the state which was undone last
+    pub fn redo(&mut self) -> Option<()> {
+        self.travel(History::redo)
+    }
+
+    fn travel<F>(&mut self, f: F) -> Option<()>
+    where
+        F: FnOnce(&mut History, &mut history::State) -> Option<()>,
+    {
+        let history = self.history.as_mut()?;
+        let mut state = history::State {
+            board: std::mem::replace(&mut self.board, Board::create(0, 0)),
+            generation: self.generation,
+        };
+        let result = f(history, &mut state);
+        self.board = state.board;
+        self.generation = state.generation;
+        if let Some(ages) = &mut self.ages {
+            *ages = CellAges::create(&self.board);
+        }
+        // Universe also holds cells outside of the board, which would not
+        // match the restored board
+        if result.is_some() && self.hashlife.is_some() {
+            self.enable_hashlife();
+        }
+        result
+    }
+}
+
+impl Game<'_> {
+    // Starts counting how long cells are alive or dead
+    pub fn enable_ages(&mut self) {
+        if self.ages.is_none() {
+            self.ages = Some(CellAges::create(&self.board));
+        }
+    }
+
+    pub fn disable_ages(&mut self) {
+        self.ages = None;
+    }
+
+    pub fn ages(&self) -> Option<&CellAges> {
+        self.ages.as_ref()
+    }
+
+    fn update_ages(&mut self) {
+        if let Some(ages) = &mut self.ages {
+            ages.update(&self.board);
+        }
+    }
+
+    fn edit_ages(&mut self) {
+        if let Some(ages) = &mut self.ages {
+            ages.edit(&self.board);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider_game(topology: Topology) -> Game<'static> {
+        let mut game = Game::create(16, 16, rules::Conway {}).with_topology(topology);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.board.set(x, y, true);
+        }
+        game
+    }
+
+    #[test]
+    fn jumps_only_with_hashlife() {
+        let mut game = glider_game(Topology::Plane);
+        assert!(game.step_pow2(3).is_none());
+        assert!(game.enable_hashlife().is_some());
+        let mut stepped = glider_game(Topology::Plane);
+        for _ in 0..8 {
+            stepped.step();
+        }
+        assert!(game.step_pow2(3).is_some());
+        assert_eq!(game.board, stepped.board);
+        assert_eq!(game.generation, 8);
+
+        // Hashlife is dropped once the board gets edges it can't follow
+        game.board.set_topology(Topology::Torus);
+        assert!(game.step_pow2(40).is_none());
+        assert!(game.hashlife().is_none());
+        assert_eq!(game.generation, 8);
+        game.step();
+        assert_eq!(game.generation, 9);
+    }
+
+    #[test]
+    fn edited_cells_start_with_age_zero() {
+        let mut game = Game::create(8, 8, rules::Conway {});
+        game.enable_ages();
+        game.step();
+        game.step();
+        game.edit(|board| board.set(3, 3, true));
+        let ages = game.ages().unwrap();
+        assert_eq!(ages.get(3, 3), Some(0));
+        assert_eq!(ages.get(0, 0), Some(ages::NEVER_ALIVE));
     }
 }
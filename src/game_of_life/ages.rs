@@ -0,0 +1,107 @@
+// Number of generations each cell has been alive, or dead since it died,
+// updated after every step and every edit
+
+use super::Board;
+
+// Age of cells which were never alive
+pub const NEVER_ALIVE: u32 = u32::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellAges {
+    ages: Vec<u32>,
+    // Board the ages correspond to
+    board: Board,
+}
+
+impl CellAges {
+    // Live cells of the board start with age 0
+    pub fn create(board: &Board) -> Self {
+        let mut ages = vec![NEVER_ALIVE; board.width() * board.height()];
+        for (_, (x, y)) in board.indexed_iter().filter(|(&cell, _)| cell) {
+            ages[y as usize * board.width() + x as usize] = 0;
+        }
+        Self {
+            ages,
+            board: board.clone(),
+        }
+    }
+
+    // Generations since the cell was born if it is alive, since it died
+    // otherwise, cells born or died in the last step have age 0
+    pub fn get(&self, x: i32, y: i32) -> Option<u32> {
+        self.board.get(x, y)?;
+        Some(self.ages[y as usize * self.board.width() + x as usize])
+    }
+
+    // Counts one generation between the last board and `board`, boards
+    // of different size start over
+    pub fn update(&mut self, board: &Board) {
+        self.compare(board, |age| age.saturating_add(1));
+    }
+
+    // Cells changed without a step, e.g. drawn by hand, start with age 0,
+    // the rest keep their age
+    pub fn edit(&mut self, board: &Board) {
+        self.compare(board, |age| age);
+    }
+
+    // Cells which differ from the last board get age 0, the rest `unchanged`
+    // of their age
+    fn compare<F>(&mut self, board: &Board, unchanged: F)
+    where
+        F: Fn(u32) -> u32,
+    {
+        if board.width() != self.board.width() || board.height() != self.board.height() {
+            *self = CellAges::create(board);
+            return;
+        }
+        let width = board.width();
+        let cells = board.indexed_iter().zip(self.board.indexed_iter());
+        for ((&now, (x, y)), (&before, _)) in cells {
+            let age = &mut self.ages[y as usize * width + x as usize];
+            *age = if now == before { unchanged(*age) } else { 0 };
+        }
+        self.board.clone_from(board);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_generations() {
+        let mut board = Board::create(3, 1);
+        board.set(0, 0, true);
+        let mut ages = CellAges::create(&board);
+        board.set(1, 0, true);
+        ages.update(&board);
+        board.set(0, 0, false);
+        ages.update(&board);
+        assert_eq!(ages.get(0, 0), Some(0));
+        assert_eq!(ages.get(1, 0), Some(1));
+        assert_eq!(ages.get(2, 0), Some(NEVER_ALIVE));
+        assert_eq!(ages.get(3, 0), None);
+        ages.update(&board);
+        assert_eq!(ages.get(0, 0), Some(1));
+        assert_eq!(ages.get(1, 0), Some(2));
+
+        ages.update(&Board::create(2, 2));
+        assert_eq!(ages.get(1, 1), Some(NEVER_ALIVE));
+    }
+
+    #[test]
+    fn edits_reset_changed_cells() {
+        let mut board = Board::create(3, 1);
+        board.set(0, 0, true);
+        let mut ages = CellAges::create(&board);
+        ages.update(&board);
+        ages.update(&board);
+        board.set(1, 0, true);
+        ages.edit(&board);
+        assert_eq!(ages.get(0, 0), Some(2));
+        assert_eq!(ages.get(1, 0), Some(0));
+        ages.update(&board);
+        assert_eq!(ages.get(1, 0), Some(1));
+    }
+}
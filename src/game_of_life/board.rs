@@ -1,9 +1,25 @@
+use std::cell::Cell;
 use std::convert::TryFrom;
 
-#[derive(Debug, Clone)]
+use super::Topology;
+
+pub(super) const WORD_BITS: usize = u64::BITS as usize;
+
+// Largest width and height accepted from files and the command line
+pub const MAX_SIZE: usize = 1 << 16;
+
+// Cells are bit-packed row by row, each row starts on a new word,
+// bits past the width of the board are always zero
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Board {
-    pub(super) cells: Vec<bool>,
+    pub(super) words: Vec<u64>,
+    pub(super) width: usize,
     pub(super) height: usize,
+    pub(super) topology: Topology,
+    // State of every cell row by row for rules with more than two states,
+    // 0 is dead, 1 alive and the rest are dying cells, which count as dead
+    // for the bits above
+    pub(super) states: Option<Vec<u8>>,
 }
 
 impl Board {
@@ -17,21 +33,64 @@ impl Board {
             "Board size has to be representable by i32"
         );
         Board {
-            cells: vec![false; width * height],
+            words: vec![0; words_per_row(width) * height],
+            width,
             height,
+            topology: Topology::default(),
+            states: None,
         }
     }
 
+    pub fn with_topology(mut self, topology: Topology) -> Board {
+        self.topology = topology;
+        self
+    }
+
     pub fn clear(&mut self) {
-        for cell in &mut self.cells {
-            *cell = false;
+        for word in &mut self.words {
+            *word = 0;
         }
+        self.states = None;
     }
 
     pub fn randomize(&mut self) {
-        for cell in &mut self.cells {
-            *cell = rand::random();
+        for word in &mut self.words {
+            *word = rand::random();
         }
+        self.clear_padding();
+        self.states = None;
+    }
+
+    // Same seed and density always give the same board
+    pub fn randomize_seeded(&mut self, seed: u64, density: f64) {
+        let size = (self.width, self.height);
+        self.randomize_rect_seeded(seed, density, (0, 0), size)
+            .expect("Internal logic error");
+    }
+
+    // Fills rectangle of `size` cells with top left corner at `pos`, cells
+    // outside of it are left untouched, fails if it doesn't fit the board
+    pub fn randomize_rect_seeded(
+        &mut self,
+        seed: u64,
+        density: f64,
+        pos: (i32, i32),
+        size: (usize, usize),
+    ) -> Option<()> {
+        use rand::{Rng, SeedableRng};
+        let x = usize::try_from(pos.0).ok()?;
+        let y = usize::try_from(pos.1).ok()?;
+        if x.checked_add(size.0)? > self.width || y.checked_add(size.1)? > self.height {
+            return None;
+        }
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let density = density.clamp(0.0, 1.0);
+        for y in y..y + size.1 {
+            for x in x..x + size.0 {
+                self.set(x, y, rng.gen_bool(density));
+            }
+        }
+        Some(())
     }
 
     pub fn create_random(width: usize, height: usize) -> Board {
@@ -41,18 +100,45 @@ impl Board {
     }
 
     pub fn width(&self) -> usize {
-        if self.height == 0 {
-            assert_eq!(self.cells.len(), 0);
-            return 0;
-        }
-        assert_eq!(self.cells.len() % self.height, 0);
-        self.cells.len() / self.height
+        self.width
     }
     pub fn height(&self) -> usize {
         self.height
     }
 
-    fn get_index<T>(&self, x: T, y: T) -> Option<usize>
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn population(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    // Cell at position which may lie outside of the board, resolved using the topology
+    pub fn get_wrapped(&self, x: i32, y: i32) -> bool {
+        let size = (self.width() as i32, self.height() as i32);
+        self.topology
+            .map(size, (x, y))
+            .and_then(|(x, y)| self.get(x, y).copied())
+            .unwrap_or(false)
+    }
+
+    // State of the cell at position resolved like in `get_wrapped`
+    pub fn get_state_wrapped(&self, x: i32, y: i32) -> u8 {
+        let size = (self.width() as i32, self.height() as i32);
+        self.topology
+            .map(size, (x, y))
+            .and_then(|(x, y)| self.get_state(x, y))
+            .unwrap_or(0)
+    }
+
+    fn get_index<T>(&self, x: T, y: T) -> Option<(usize, u64)>
     where
         T: std::convert::TryInto<usize>,
     {
@@ -64,62 +150,337 @@ impl Board {
         if y >= self.height() {
             return None;
         }
-        Some(x * self.height + y)
+        let index = y * self.words_per_row() + x / WORD_BITS;
+        Some((index, 1 << (x % WORD_BITS)))
     }
 
     pub fn get<T>(&self, x: T, y: T) -> Option<&bool>
     where
         T: std::convert::TryInto<usize>,
     {
-        let index = self.get_index(x, y)?;
-        self.cells.get(index)
+        let (index, mask) = self.get_index(x, y)?;
+        Some(if self.words[index] & mask != 0 {
+            &true
+        } else {
+            &false
+        })
     }
 
-    pub fn get_mut<T>(&mut self, x: T, y: T) -> Option<&mut bool>
+    pub fn set<T>(&mut self, x: T, y: T, value: bool) -> Option<()>
     where
         T: std::convert::TryInto<usize>,
     {
-        let index = self.get_index(x, y)?;
-        self.cells.get_mut(index)
+        let (x, y): (usize, usize) = (x.try_into().ok()?, y.try_into().ok()?);
+        let (index, mask) = self.get_index(x, y)?;
+        if value {
+            self.words[index] |= mask;
+        } else {
+            self.words[index] &= !mask;
+        }
+        if let Some(states) = &mut self.states {
+            states[y * self.width + x] = value.into();
+        }
+        Some(())
     }
 
-    pub fn set<T>(&mut self, x: T, y: T, value: bool) -> Option<()>
+    pub fn toggle<T>(&mut self, x: T, y: T) -> Option<()>
     where
         T: std::convert::TryInto<usize>,
     {
-        *self.get_mut(x, y)? = value;
-        Some(())
+        let (x, y): (usize, usize) = (x.try_into().ok()?, y.try_into().ok()?);
+        let value = !*self.get(x, y)?;
+        self.set(x, y, value)
     }
 
-    pub fn toggle<T>(&mut self, x: T, y: T) -> Option<()>
+    // Whether the board holds states of rules with more than two states
+    pub fn has_states(&self) -> bool {
+        self.states.is_some()
+    }
+
+    // State of the cell, 0 is dead, 1 alive and higher states are dying cells
+    pub fn get_state<T>(&self, x: T, y: T) -> Option<u8>
+    where
+        T: std::convert::TryInto<usize>,
+    {
+        let (x, y): (usize, usize) = (x.try_into().ok()?, y.try_into().ok()?);
+        let alive = *self.get(x, y)?;
+        match &self.states {
+            Some(states) => Some(states[y * self.width + x]),
+            None => Some(alive.into()),
+        }
+    }
+
+    pub fn set_state<T>(&mut self, x: T, y: T, state: u8) -> Option<()>
     where
         T: std::convert::TryInto<usize>,
     {
-        let cell = self.get_mut(x, y)?;
-        *cell = !*cell;
+        let (x, y): (usize, usize) = (x.try_into().ok()?, y.try_into().ok()?);
+        self.set(x, y, state == 1)?;
+        if state > 1 {
+            self.enable_states();
+        }
+        if let Some(states) = &mut self.states {
+            states[y * self.width + x] = state;
+        }
         Some(())
     }
+
+    // Makes the board hold states, each cell starts in state 0 or 1
+    pub fn enable_states(&mut self) {
+        if self.states.is_none() {
+            let states = (0..self.height)
+                .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+                .map(|(x, y)| u8::from(*self.get(x, y).expect("Internal logic error")))
+                .collect();
+            self.states = Some(states);
+        }
+    }
+
+    // Dying cells become dead
+    pub fn disable_states(&mut self) {
+        self.states = None;
+    }
+
+    // Creates board of the given size and the same topology with the cells
+    // of this board placed so that the centres of both boards match
+    pub fn resized(&self, width: usize, height: usize) -> Board {
+        let mut board = Board::create(width, height).with_topology(self.topology);
+        if self.has_states() {
+            board.enable_states();
+        }
+        let shift_x = (width as i32 - self.width as i32) / 2;
+        let shift_y = (height as i32 - self.height as i32) / 2;
+        for (_, (x, y)) in self.indexed_iter() {
+            let state = self.get_state(x, y).expect("Internal logic error");
+            if state != 0 {
+                board.set_state(x + shift_x, y + shift_y, state);
+            }
+        }
+        board
+    }
+
+    // Creates board of the same size and topology with every cell computed by `f`
+    pub fn map<F>(&self, mut f: F) -> Board
+    where
+        F: FnMut(&bool, (i32, i32)) -> bool,
+    {
+        let mut board = Board::create(self.width, self.height).with_topology(self.topology);
+        for (cell, (x, y)) in self.indexed_iter() {
+            if f(cell, (x, y)) {
+                board.set(x, y, true);
+            }
+        }
+        board
+    }
+}
+
+pub(super) fn words_per_row(width: usize) -> usize {
+    width.div_ceil(WORD_BITS)
+}
+
+impl Board {
+    pub(super) fn words_per_row(&self) -> usize {
+        words_per_row(self.width)
+    }
+
+    pub(super) fn row(&self, y: usize) -> &[u64] {
+        let words_per_row = self.words_per_row();
+        &self.words[y * words_per_row..(y + 1) * words_per_row]
+    }
+
+    // Mask of valid bits in the last word of a row
+    pub(super) fn last_word_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    pub(super) fn clear_padding(&mut self) {
+        let words_per_row = self.words_per_row();
+        if words_per_row == 0 {
+            return;
+        }
+        let mask = self.last_word_mask();
+        for row in self.words.chunks_exact_mut(words_per_row) {
+            row[words_per_row - 1] &= mask;
+        }
+    }
+}
+
+// Mutable reference to a bit-packed cell, the value is written back to the
+// board when it is dropped
+pub struct CellMut<'a> {
+    word: &'a Cell<u64>,
+    mask: u64,
+    state: Option<&'a Cell<u8>>,
+    value: bool,
+}
+
+impl std::ops::Deref for CellMut<'_> {
+    type Target = bool;
+
+    fn deref(&self) -> &bool {
+        &self.value
+    }
+}
+
+impl std::ops::DerefMut for CellMut<'_> {
+    fn deref_mut(&mut self) -> &mut bool {
+        &mut self.value
+    }
+}
+
+impl Drop for CellMut<'_> {
+    // Cells which didn't change keep their state
+    fn drop(&mut self) {
+        let word = self.word.get();
+        if (word & self.mask != 0) == self.value {
+            return;
+        }
+        self.word.set(word ^ self.mask);
+        if let Some(state) = self.state {
+            state.set(self.value.into());
+        }
+    }
+}
+
+// Words and states of the board which can be shared by several `CellMut`
+struct SharedCells<'a> {
+    words: &'a [Cell<u64>],
+    states: Option<&'a [Cell<u8>]>,
+    width: usize,
+    words_per_row: usize,
+}
+
+impl<'a> SharedCells<'a> {
+    fn create(board: &'a mut Board) -> Self {
+        let (width, words_per_row) = (board.width, board.words_per_row());
+        SharedCells {
+            words: Cell::from_mut(&mut board.words[..]).as_slice_of_cells(),
+            states: board
+                .states
+                .as_mut()
+                .map(|states| Cell::from_mut(&mut states[..]).as_slice_of_cells()),
+            width,
+            words_per_row,
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> CellMut<'a> {
+        let word = &self.words[y * self.words_per_row + x / WORD_BITS];
+        let mask = 1 << (x % WORD_BITS);
+        CellMut {
+            word,
+            mask,
+            state: self.states.map(|states| &states[y * self.width + x]),
+            value: word.get() & mask != 0,
+        }
+    }
+}
+
+// Cells of a column of the board from top to bottom
+pub struct Column<'a> {
+    board: &'a Board,
+    x: usize,
+    rows: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for Column<'a> {
+    type Item = &'a bool;
+
+    fn next(&mut self) -> Option<&'a bool> {
+        let y = self.rows.next()?;
+        self.board.get(self.x, y)
+    }
+}
+
+pub struct Columns<'a> {
+    board: &'a Board,
+    columns: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for Columns<'a> {
+    type Item = Column<'a>;
+
+    fn next(&mut self) -> Option<Column<'a>> {
+        Some(Column {
+            board: self.board,
+            x: self.columns.next()?,
+            rows: 0..self.board.height,
+        })
+    }
+}
+
+pub struct ColumnMut<'a> {
+    cells: std::rc::Rc<SharedCells<'a>>,
+    x: usize,
+    rows: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for ColumnMut<'a> {
+    type Item = CellMut<'a>;
+
+    fn next(&mut self) -> Option<CellMut<'a>> {
+        Some(self.cells.cell(self.x, self.rows.next()?))
+    }
+}
+
+pub struct ColumnsMut<'a> {
+    cells: std::rc::Rc<SharedCells<'a>>,
+    height: usize,
+    columns: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for ColumnsMut<'a> {
+    type Item = ColumnMut<'a>;
+
+    fn next(&mut self) -> Option<ColumnMut<'a>> {
+        Some(ColumnMut {
+            cells: self.cells.clone(),
+            x: self.columns.next()?,
+            rows: 0..self.height,
+        })
+    }
 }
 
+// Boards are iterated by columns
 impl<'a> IntoIterator for &'a Board {
-    type Item = &'a [bool];
-    type IntoIter = std::slice::ChunksExact<'a, bool>;
+    type Item = Column<'a>;
+    type IntoIter = Columns<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.cells.as_slice().chunks_exact(self.height)
+        Columns {
+            board: self,
+            columns: 0..self.width,
+        }
     }
 }
 
 impl<'a> IntoIterator for &'a mut Board {
-    type Item = &'a mut [bool];
-    type IntoIter = std::slice::ChunksExactMut<'a, bool>;
+    type Item = ColumnMut<'a>;
+    type IntoIter = ColumnsMut<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.cells.as_mut_slice().chunks_exact_mut(self.height)
+        let (width, height) = (self.width, self.height);
+        ColumnsMut {
+            cells: std::rc::Rc::new(SharedCells::create(self)),
+            height,
+            columns: 0..width,
+        }
     }
 }
 
 impl Board {
+    pub fn get_mut<T>(&mut self, x: T, y: T) -> Option<CellMut<'_>>
+    where
+        T: std::convert::TryInto<usize>,
+    {
+        let (x, y): (usize, usize) = (x.try_into().ok()?, y.try_into().ok()?);
+        self.get_index(x, y)?;
+        Some(SharedCells::create(self).cell(x, y))
+    }
+
     pub fn iter(&self) -> <&Self as std::iter::IntoIterator>::IntoIter {
         self.into_iter()
     }
@@ -129,13 +490,61 @@ impl Board {
     pub fn indexed_iter(&self) -> impl '_ + Iterator<Item = (&bool, (i32, i32))> {
         self.iter()
             .zip(0..)
-            .map(|(row, x)| row.iter().zip((0..).map(move |y| (x, y))))
-            .flatten()
+            .flat_map(|(column, x)| column.zip((0..).map(move |y| (x, y))))
     }
-    pub fn indexed_iter_mut(&mut self) -> impl '_ + Iterator<Item = (&mut bool, (i32, i32))> {
+    pub fn indexed_iter_mut(&mut self) -> impl '_ + Iterator<Item = (CellMut<'_>, (i32, i32))> {
         self.iter_mut()
             .zip(0..)
-            .map(|(row, x)| row.iter_mut().zip((0..).map(move |y| (x, y))))
-            .flatten()
+            .flat_map(|(column, x)| column.zip((0..).map(move |y| (x, y))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_board() {
+        let random = |seed| {
+            let mut board = Board::create(70, 30);
+            board.randomize_seeded(seed, 0.5);
+            board
+        };
+        assert_eq!(random(7), random(7));
+        assert_ne!(random(7), random(8));
+    }
+
+    #[test]
+    fn respects_density() {
+        let mut board = Board::create(100, 100);
+        board.randomize_seeded(1, 0.0);
+        assert_eq!(board.population(), 0);
+        board.randomize_seeded(1, 1.0);
+        assert_eq!(board.population(), 100 * 100);
+        board.randomize_seeded(1, 0.3);
+        let population = board.population();
+        assert!((2700..3300).contains(&population), "{}", population);
+        // Padding bits past the width stay clear
+        assert_eq!(board.words.len(), 2 * 100);
+        assert!(board
+            .words
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(|word| word >> 36 == 0));
+    }
+
+    #[test]
+    fn randomizes_only_the_rectangle() {
+        let mut board = Board::create(10, 8);
+        board.set(0, 0, true);
+        board.randomize_rect_seeded(3, 1.0, (2, 3), (4, 5)).unwrap();
+        for (&cell, (x, y)) in board.indexed_iter() {
+            let inside = (2..6).contains(&x) && (3..8).contains(&y);
+            assert_eq!(cell, inside || (x, y) == (0, 0), "{} {}", x, y);
+        }
+        assert_eq!(board.randomize_rect_seeded(3, 1.0, (2, 4), (4, 5)), None);
+        assert_eq!(board.randomize_rect_seeded(3, 1.0, (-1, 0), (1, 1)), None);
+        assert_eq!(board.population(), 4 * 5 + 1);
     }
 }
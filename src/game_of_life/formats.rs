@@ -0,0 +1,206 @@
+pub mod life105;
+pub mod life106;
+pub mod plaintext;
+pub mod rle;
+
+use std::collections::HashMap;
+
+use super::rules::Rule;
+use super::{Board, Shape, Topology};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // Both line and column are counted from 1
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Bounded grid the pattern lives on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+    pub topology: Topology,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Shape together with the metadata stored in pattern files
+#[derive(Default)]
+pub struct Pattern {
+    pub shape: Shape,
+    // States of the points of the shape which are not alive, e.g. dying cells
+    // of Generations rules, the rest of the points are alive
+    pub states: HashMap<(i32, i32), u8>,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<Box<dyn Rule>>,
+    // Generation the pattern was saved at
+    pub generation: Option<u64>,
+    pub grid: Option<Grid>,
+}
+
+impl Pattern {
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            ..Default::default()
+        }
+    }
+
+    // Cells of the board which are not dead, including their states
+    pub fn from_board(board: &Board) -> Self {
+        let mut pattern = Pattern::new(Shape::from_board_states(board));
+        let (pos_x, pos_y) = pattern.shape.pos();
+        for &(x, y) in pattern.shape.points() {
+            let state = board
+                .get_state(pos_x + x, pos_y + y)
+                .expect("Internal logic error");
+            if state > 1 {
+                pattern.states.insert((x, y), state);
+            }
+        }
+        pattern
+    }
+
+    // Points of the shape which are alive
+    pub fn live_points(&self) -> impl Iterator<Item = &(i32, i32)> {
+        self.shape
+            .points()
+            .iter()
+            .filter(move |&&point| self.state(point) == 1)
+    }
+
+    // State of a point of the shape
+    pub fn state(&self, point: (i32, i32)) -> u8 {
+        self.states.get(&point).copied().unwrap_or(1)
+    }
+
+    // Places the pattern with its states centred at `pos`, see `Shape::add`
+    pub fn add(&self, board: &mut Board, pos: (i32, i32)) -> Option<()> {
+        let (x, y) = self.shape.center_at_pos(pos);
+        self.shape
+            .points()
+            .iter()
+            .map(|&point| board.set_state(x + point.0, y + point.1, self.state(point)))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pattern")
+            .field("shape", &self.shape)
+            .field("states", &self.states)
+            .field("name", &self.name)
+            .field("author", &self.author)
+            .field("comments", &self.comments)
+            .field("rule", &self.rule.as_ref().map(|rule| rule.notation()))
+            .field("generation", &self.generation)
+            .field("grid", &self.grid)
+            .finish()
+    }
+}
+
+// Rows of the bounding box of the shape, one line each, formats without
+// states show only the live cells
+fn grid_to_string(pattern: &Pattern, alive: char, dead: char) -> String {
+    let (width, height) = pattern.shape.dimensions();
+    let mut grid = vec![vec![dead; width as usize]; height as usize];
+    for &(x, y) in pattern.live_points() {
+        grid[y as usize][x as usize] = alive;
+    }
+    grid.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .chain(std::iter::once('\n'))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+}
+
+impl Format {
+    // Format given by the header of the file, RLE has no distinct header
+    pub fn detect(input: &str) -> Option<Format> {
+        let first_line = input.lines().find(|line| !line.trim().is_empty())?;
+        if first_line.starts_with("#Life 1.05") {
+            Some(Format::Life105)
+        } else if first_line.starts_with("#Life 1.06") {
+            Some(Format::Life106)
+        } else if first_line.starts_with('!') {
+            Some(Format::Plaintext)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_path(path: &std::path::Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            _ => None,
+        }
+    }
+
+    pub fn parse(self, input: &str) -> Result<Pattern, ParseError> {
+        match self {
+            Format::Rle => rle::parse(input),
+            Format::Plaintext => plaintext::parse(input),
+            Format::Life105 => life105::parse(input),
+            Format::Life106 => life106::parse(input),
+        }
+    }
+
+    pub fn write(self, pattern: &Pattern) -> String {
+        match self {
+            Format::Rle => rle::write(pattern),
+            Format::Plaintext => plaintext::write(pattern),
+            Format::Life105 => life105::write(pattern),
+            Format::Life106 => life106::write(pattern),
+        }
+    }
+}
+
+pub fn read_file(path: &std::path::Path) -> Result<Pattern, Box<dyn std::error::Error>> {
+    let input = std::fs::read_to_string(path)?;
+    let format = Format::detect(&input)
+        .or_else(|| Format::from_path(path))
+        .unwrap_or(Format::Rle);
+    format
+        .parse(&input)
+        .map_err(|e| format!("{}:{}", path.display(), e).into())
+}
+
+// Format is chosen by the extension, RLE by default
+pub fn write_file(path: &std::path::Path, pattern: &Pattern) -> std::io::Result<()> {
+    let format = Format::from_path(path).unwrap_or(Format::Rle);
+    std::fs::write(path, format.write(pattern))
+}
@@ -0,0 +1,188 @@
+// Life 1.05 format, cell blocks placed relative to the centre, e.g. glider:
+//   #Life 1.05
+//   #D Glider
+//   #N
+//   #P -1 -1
+//   .*.
+//   ..*
+//   ***
+
+use super::{ParseError, Pattern};
+use crate::game_of_life::{rules, Shape};
+
+const HEADER: &str = "#Life 1.05";
+
+pub fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let mut points = Vec::new();
+    let mut lines = (1..).zip(input.lines());
+    match lines.next() {
+        Some((_, line)) if line.trim_end() == HEADER => {}
+        _ => {
+            return Err(ParseError::new(
+                1,
+                1,
+                format!("Expected '{}' header", HEADER),
+            ))
+        }
+    }
+
+    let mut block: Option<(i32, i32)> = None;
+    for (line_no, line) in lines {
+        let line = line.trim_end();
+        if let Some(directive) = line.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let kind = chars.next();
+            let text = chars.as_str().trim();
+            match kind {
+                Some('D') | Some('C') => {
+                    // Name and author are stored as descriptions, as there are
+                    // no dedicated directives for them
+                    if let Some(name) = text.strip_prefix("Name:") {
+                        pattern.name = Some(name.trim().into());
+                    } else if let Some(author) = text.strip_prefix("Author:") {
+                        pattern.author = Some(author.trim().into());
+                    } else {
+                        pattern.comments.push(text.into());
+                    }
+                }
+                Some('N') => pattern.rule = Some(Box::new(rules::Conway {})),
+                Some('R') => {
+                    let rule = rules::parse(text)
+                        .map_err(|e| ParseError::new(line_no, 4, e.to_string()))?;
+                    pattern.rule = Some(rule);
+                }
+                Some('P') => {
+                    let mut coords = text.split_whitespace().map(str::parse::<i32>);
+                    match (coords.next(), coords.next(), coords.next()) {
+                        (Some(Ok(x)), Some(Ok(y)), None) => block = Some((x, y)),
+                        _ => {
+                            let message = "Expected block position in format '#P x y'";
+                            return Err(ParseError::new(line_no, 4, message));
+                        }
+                    }
+                }
+                _ => return Err(ParseError::new(line_no, 2, "Unknown directive")),
+            }
+            continue;
+        }
+        let (block_x, block_y) = match &mut block {
+            Some(block) => block,
+            None if line.is_empty() => continue,
+            None => return Err(ParseError::new(line_no, 1, "Cells have to follow '#P x y'")),
+        };
+        for (column, (x, c)) in (1..).zip((0..).zip(line.chars())) {
+            match c {
+                '*' => points.push((*block_x + x, *block_y)),
+                '.' => {}
+                c => {
+                    let message = format!("Unexpected character '{}', expected '.' or '*'", c);
+                    return Err(ParseError::new(line_no, column, message));
+                }
+            }
+        }
+        *block_y += 1;
+    }
+    pattern.shape = Shape::new(points);
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", HEADER);
+    if let Some(name) = &pattern.name {
+        out += &format!("#D Name: {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        out += &format!("#D Author: {}\n", author);
+    }
+    for comment in &pattern.comments {
+        out += &format!("#D {}\n", comment);
+    }
+    // Other rules can't be written, leaving them out at least doesn't claim
+    // the pattern is meant for Conway's Life
+    match pattern.rule.as_ref().and_then(|rule| rule.life_like()) {
+        Some(rule) if rule == rules::LifeLike::conway() => out += "#N\n",
+        Some(rule) => {
+            let counts = |counts: &mut dyn Iterator<Item = u8>| {
+                counts.map(|count| count.to_string()).collect::<String>()
+            };
+            out += &format!(
+                "#R {}/{}\n",
+                counts(&mut rule.survival()),
+                counts(&mut rule.birth())
+            );
+        }
+        None => {}
+    }
+    let (x, y) = pattern.shape.pos();
+    out += &format!("#P {} {}\n", x, y);
+    out += &super::grid_to_string(pattern, '*', '.');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut pattern = Pattern::new(Shape::new(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]));
+        pattern.name = Some("Glider".into());
+        pattern.author = Some("Richard K. Guy".into());
+        pattern.comments.push("Smallest spaceship".into());
+        let read = parse(&write(&pattern)).unwrap();
+        assert_eq!(read.name, pattern.name);
+        assert_eq!(read.author, pattern.author);
+        assert_eq!(read.comments, pattern.comments);
+        assert_eq!(write(&read), write(&pattern));
+    }
+
+    #[test]
+    fn writes_only_life_like_rules() {
+        let notation = |rule: &str| {
+            let mut pattern = Pattern::new(Shape::new(vec![(0, 0)]));
+            pattern.rule = Some(rules::parse(rule).unwrap());
+            let text = write(&pattern);
+            let read = parse(&text).unwrap();
+            (text, read.rule.and_then(|rule| rule.notation()))
+        };
+        let (text, rule) = notation("B3/S23");
+        assert!(text.contains("#N\n"));
+        assert_eq!(rule.as_deref(), Some("B3/S23"));
+        let (text, rule) = notation("B36/S23");
+        assert!(text.contains("#R 23/36\n"));
+        assert_eq!(rule.as_deref(), Some("B36/S23"));
+        for &rule in &["345/2/4", "B2-a/S12", "R2,C0,M1,S2..3,B3..3,NM"] {
+            let (text, read) = notation(rule);
+            assert!(!text.contains("#N") && !text.contains("#R"), "{}", text);
+            assert_eq!(read, None);
+        }
+    }
+
+    #[test]
+    fn reads_blocks() {
+        let text = "#Life 1.05\n#P -1 -1\n.*.\n..*\n#P 5 5\n**\n";
+        let pattern = parse(text).unwrap();
+        let (pos_x, pos_y) = pattern.shape.pos();
+        let mut points: Vec<_> = pattern
+            .live_points()
+            .map(|&(x, y)| (pos_x + x, pos_y + y))
+            .collect();
+        points.sort_unstable();
+        assert_eq!(points, vec![(0, -1), (1, 0), (5, 5), (6, 5)]);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let position = |text: &str| {
+            let error = parse(text).unwrap_err();
+            (error.line, error.column)
+        };
+        assert_eq!(position("#Life 1.06\n"), (1, 1));
+        assert_eq!(position("#Life 1.05\n*\n"), (2, 1));
+        assert_eq!(position("#Life 1.05\n#P 0 0\n..*\n.*x\n"), (4, 3));
+        assert_eq!(position("#Life 1.05\n#P 0\n"), (2, 4));
+        assert_eq!(position("#Life 1.05\n#D ok\n#X\n"), (3, 2));
+        assert_eq!(position("#Life 1.05\n#R 23/3/x\n"), (2, 4));
+    }
+}
@@ -0,0 +1,95 @@
+// Life 1.06 format, one live cell per line, e.g. glider:
+//   #Life 1.06
+//   0 -1
+//   1 0
+//   -1 1
+//   0 1
+//   1 1
+
+use super::{ParseError, Pattern};
+use crate::game_of_life::Shape;
+
+const HEADER: &str = "#Life 1.06";
+
+pub fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut lines = (1..).zip(input.lines());
+    match lines.next() {
+        Some((_, line)) if line.trim_end() == HEADER => {}
+        _ => {
+            return Err(ParseError::new(
+                1,
+                1,
+                format!("Expected '{}' header", HEADER),
+            ))
+        }
+    }
+
+    let mut points = Vec::new();
+    for (line_no, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut coords = Vec::new();
+        let mut rest = line;
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let token = rest[start..].split_whitespace().next().unwrap_or_default();
+            let column = line[..line.len() - rest.len() + start].chars().count() + 1;
+            let coord = token.parse::<i32>().map_err(|_| {
+                ParseError::new(line_no, column, format!("Invalid coordinate '{}'", token))
+            })?;
+            coords.push((coord, column));
+            rest = &rest[start + token.len()..];
+        }
+        match coords.as_slice() {
+            [(x, _), (y, _)] => points.push((*x, *y)),
+            [_] => {
+                let column = line.trim_end().chars().count() + 1;
+                return Err(ParseError::new(line_no, column, "Missing y coordinate"));
+            }
+            [_, _, (_, column), ..] => {
+                return Err(ParseError::new(line_no, *column, "Expected only 'x y'"))
+            }
+            [] => unreachable!(),
+        }
+    }
+    Ok(Pattern::new(Shape::new(points)))
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", HEADER);
+    let (pos_x, pos_y) = pattern.shape.pos();
+    for (x, y) in pattern.live_points() {
+        out += &format!("{} {}\n", pos_x + x, pos_y + y);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let pattern = parse("#Life 1.06\n0 -1\n  1   0\n\n-1 1\n0 1\n1 1\n").unwrap();
+        let (pos_x, pos_y) = pattern.shape.pos();
+        assert_eq!((pos_x, pos_y), (-1, -1));
+        assert_eq!(pattern.shape.points().len(), 5);
+        let text = write(&pattern);
+        let read = parse(&text).unwrap();
+        assert_eq!(read.shape.pos(), pattern.shape.pos());
+        assert_eq!(write(&read), text);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let position = |text: &str| {
+            let error = parse(text).unwrap_err();
+            (error.line, error.column)
+        };
+        assert_eq!(position("#Life 1.05\n"), (1, 1));
+        assert_eq!(position("#Life 1.06\n0 0\n1 x\n"), (3, 3));
+        assert_eq!(position("#Life 1.06\n  12\n"), (2, 5));
+        assert_eq!(position("#Life 1.06\n1 2 3\n"), (2, 5));
+        assert_eq!(position("#Life 1.06\n\u{e9} 2\n"), (2, 1));
+    }
+}
@@ -0,0 +1,94 @@
+// Plaintext format (.cells), e.g. glider:
+//   !Name: Glider
+//   .O.
+//   ..O
+//   OOO
+
+use super::{ParseError, Pattern};
+use crate::game_of_life::Shape;
+
+pub fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let mut points = Vec::new();
+    let mut y = 0;
+    for (line_no, line) in (1..).zip(input.lines()) {
+        if let Some(comment) = line.strip_prefix('!') {
+            let comment = comment.trim();
+            if let Some(name) = comment.strip_prefix("Name:") {
+                pattern.name = Some(name.trim().into());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                pattern.author = Some(author.trim().into());
+            } else {
+                pattern.comments.push(comment.into());
+            }
+            continue;
+        }
+        for (column, (x, c)) in (1..).zip((0..).zip(line.trim_end().chars())) {
+            match c {
+                'O' | '*' => points.push((x, y)),
+                '.' => {}
+                c => {
+                    let message = format!("Unexpected character '{}', expected '.' or 'O'", c);
+                    return Err(ParseError::new(line_no, column, message));
+                }
+            }
+        }
+        y += 1;
+    }
+    pattern.shape = Shape::new(points);
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out += &format!("!Name: {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        out += &format!("!Author: {}\n", author);
+    }
+    for comment in &pattern.comments {
+        out += &format!("!{}\n", comment);
+    }
+    out += &super::grid_to_string(pattern, 'O', '.');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_glider() {
+        let pattern = parse("!Name: Glider\n!Author: Richard K. Guy\n!\n.O.\n..*\nOOO\n").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(pattern.comments, vec![String::new()]);
+        let mut points = pattern.shape.points().to_vec();
+        points.sort_unstable();
+        assert_eq!(points, vec![(0, 2), (1, 0), (1, 2), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pattern = Pattern::new(Shape::new(vec![(0, 0), (3, 0), (1, 2)]));
+        pattern.name = Some("Sparks".into());
+        pattern.comments.push("Three cells".into());
+        let text = write(&pattern);
+        assert_eq!(text, "!Name: Sparks\n!Three cells\nO..O\n....\n.O..\n");
+        let read = parse(&text).unwrap();
+        assert_eq!(read.name, pattern.name);
+        assert_eq!(read.comments, pattern.comments);
+        assert_eq!(write(&read), text);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = parse("!Name: x\n.O.\n..o\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+        let error = parse("\u{e9}O\n").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        let error = parse("O.\u{e9}O\n").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+    }
+}
@@ -0,0 +1,551 @@
+// Run length encoded format used by Golly and LifeWiki, e.g. glider:
+//   #N Glider
+//   x = 3, y = 3, rule = B3/S23
+//   bob$2bo$3o!
+
+use std::convert::TryFrom;
+
+use super::{Grid, ParseError, Pattern};
+use crate::game_of_life::{rules, Shape, Topology};
+
+const MAX_LINE_LENGTH: usize = 70;
+
+fn column(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset].chars().count() + 1
+}
+
+// Grid of Golly's bounded universes, e.g. T30,20 for a torus or K30*,20
+// for a Klein bottle with the top and bottom edges joined with a flip
+fn parse_grid(text: &str) -> Result<Grid, String> {
+    let error = || format!("Unsupported bounded grid '{}'", text);
+    let mut chars = text.chars();
+    let kind = chars.next().ok_or_else(error)?.to_ascii_uppercase();
+    let (width, height) = chars.as_str().split_once(',').ok_or_else(error)?;
+    let (width, flipped) = match width.strip_suffix('*') {
+        Some(width) => (width, true),
+        None => (width, false),
+    };
+    let topology = match (kind, flipped) {
+        ('P', false) => Topology::Plane,
+        ('T', false) => Topology::Torus,
+        ('K', true) => Topology::KleinBottle,
+        ('C', false) => Topology::CrossSurface,
+        _ => return Err(error()),
+    };
+    let size = |text: &str| text.parse().ok().filter(|&size| size > 0).ok_or_else(error);
+    Ok(Grid {
+        topology,
+        width: size(width)?,
+        height: size(height)?,
+    })
+}
+
+// None for topologies Golly has no bounded grid for
+fn grid_notation(grid: &Grid) -> Option<String> {
+    let (kind, flip) = match grid.topology {
+        Topology::Plane => ('P', ""),
+        Topology::Torus => ('T', ""),
+        Topology::KleinBottle => ('K', "*"),
+        Topology::CrossSurface => ('C', ""),
+        Topology::HorizontalCylinder | Topology::VerticalCylinder => return None,
+    };
+    Some(format!("{}{}{},{}", kind, grid.width, flip, grid.height))
+}
+
+// Rule string optionally followed by the grid, e.g. B3/S23:T30,20
+fn parse_rule(pattern: &mut Pattern, text: &str) -> Result<(), String> {
+    let (rule, grid) = match text.rsplit_once(':') {
+        Some((rule, grid)) if grid.trim().starts_with(|c: char| c.is_ascii_alphabetic()) => {
+            (rule, Some(parse_grid(grid.trim())?))
+        }
+        _ => (text, None),
+    };
+    pattern.rule = Some(rules::parse(rule.trim()).map_err(|e| e.to_string())?);
+    pattern.grid = grid;
+    Ok(())
+}
+
+// Golly's extended header, e.g. "#CXRLE Pos=-1,-1 Gen=100"
+fn parse_extended(
+    pattern: &mut Pattern,
+    offset: &mut Option<(i32, i32)>,
+    text: &str,
+) -> Result<(), String> {
+    for item in text.split_whitespace() {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| format!("Expected 'key=value', found '{}'", item))?;
+        match key {
+            "Pos" => {
+                let pos = value
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                    .ok_or_else(|| format!("Invalid position '{}'", value))?;
+                *offset = Some(pos);
+            }
+            "Gen" => {
+                let generation = value
+                    .parse()
+                    .map_err(|_| format!("Invalid generation '{}'", value))?;
+                pattern.generation = Some(generation);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_comment(
+    pattern: &mut Pattern,
+    offset: &mut Option<(i32, i32)>,
+    line: &str,
+    line_no: usize,
+) -> Result<(), ParseError> {
+    let line = line.trim_start();
+    let mut chars = line.chars();
+    chars.next();
+    let kind = chars.next();
+    let text = chars.as_str().trim();
+    if let Some(extended) = line.strip_prefix("#CXRLE") {
+        return parse_extended(pattern, offset, extended)
+            .map_err(|message| ParseError::new(line_no, 8, message));
+    }
+    match kind {
+        Some('N') => pattern.name = Some(text.into()),
+        Some('O') => pattern.author = Some(text.into()),
+        Some('R') | Some('P') => {
+            let mut coords = text.split_whitespace().map(str::parse::<i32>);
+            match (coords.next(), coords.next(), coords.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => *offset = Some((x, y)),
+                _ => {
+                    return Err(ParseError::new(
+                        line_no,
+                        3,
+                        "Expected offset in format '#R x y'",
+                    ))
+                }
+            }
+        }
+        Some('r') => {
+            parse_rule(pattern, text).map_err(|message| ParseError::new(line_no, 3, message))?
+        }
+        _ => pattern.comments.push(text.into()),
+    }
+    Ok(())
+}
+
+fn parse_header(
+    pattern: &mut Pattern,
+    line: &str,
+    line_no: usize,
+) -> Result<(i32, i32), ParseError> {
+    let mut size = (None, None);
+    let mut start = 0;
+    for item in line.split(',') {
+        let item_start = start + item.len() - item.trim_start().len();
+        let item_column = column(line, item_start);
+        start += item.len() + 1;
+        let mut parts = item.splitn(2, '=');
+        let key = parts.next().unwrap_or_default().trim();
+        let value = parts
+            .next()
+            .ok_or_else(|| {
+                ParseError::new(line_no, item_column, format!("Expected '{} = value'", key))
+            })?
+            .trim();
+        // Rule strings may contain commas, so the rule takes the rest of the line
+        if key == "rule" {
+            let value = line[item_start..]
+                .split_once('=')
+                .map_or("", |(_, value)| value);
+            parse_rule(pattern, value.trim())
+                .map_err(|message| ParseError::new(line_no, item_column, message))?;
+            break;
+        }
+        let parse_size = |value: &str| {
+            value
+                .parse::<i32>()
+                .ok()
+                .filter(|&v| v >= 0)
+                .ok_or_else(|| {
+                    ParseError::new(
+                        line_no,
+                        item_column,
+                        format!("Invalid pattern size '{}'", value),
+                    )
+                })
+        };
+        match key {
+            "x" => size.0 = Some(parse_size(value)?),
+            "y" => size.1 = Some(parse_size(value)?),
+            _ => {
+                return Err(ParseError::new(
+                    line_no,
+                    item_column,
+                    format!("Unknown header key '{}'", key),
+                ))
+            }
+        }
+    }
+    match size {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(ParseError::new(
+            line_no,
+            1,
+            "Header has to contain both 'x' and 'y'",
+        )),
+    }
+}
+
+pub fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let mut offset = None;
+    let mut size = None;
+    // Positions and states of cells which are not dead
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut run: Option<i32> = None;
+    let mut prefix: Option<u32> = None;
+    let mut line_count = 0;
+
+    'lines: for (line_no, line) in (1..).zip(input.lines()) {
+        line_count = line_no;
+        if line.trim_start().starts_with('#') {
+            parse_comment(&mut pattern, &mut offset, line, line_no)?;
+            continue;
+        }
+        let (width, height) = match size {
+            Some(size) => size,
+            None => {
+                if !line.trim().is_empty() {
+                    size = Some(parse_header(&mut pattern, line, line_no)?);
+                }
+                continue;
+            }
+        };
+
+        for (byte_offset, c) in line.char_indices() {
+            let error =
+                |message: &str| ParseError::new(line_no, column(line, byte_offset), message);
+            if let Some(digit) = c.to_digit(10) {
+                let count = run
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as i32));
+                run = Some(count.ok_or_else(|| error("Run count is too large"))?);
+                continue;
+            }
+            if c.is_whitespace() {
+                continue;
+            }
+            // States above 24 are written as a letter p-y followed by A-X
+            if let 'p'..='y' = c {
+                if prefix.is_some() {
+                    return Err(error("Expected state letter 'A'-'X'"));
+                }
+                prefix = Some(c as u32 - 'p' as u32 + 1);
+                continue;
+            }
+            let count = run.take().unwrap_or(1);
+            // Empty runs would move back to cells which were already read
+            if count == 0 {
+                return Err(error("Run count has to be greater than 0"));
+            }
+            let state = match (prefix.take(), c) {
+                (None, 'o') => 1,
+                (prefix, 'A'..='X') => {
+                    let state = prefix.unwrap_or(0) * 24 + c as u32 - 'A' as u32 + 1;
+                    u8::try_from(state).map_err(|_| error("State is too large"))?
+                }
+                (Some(_), _) => return Err(error("Expected state letter 'A'-'X'")),
+                (None, _) => 0,
+            };
+            match c {
+                'b' | '.' => x += count,
+                'o' | 'A'..='X' => {
+                    if x + count > width || y >= height {
+                        return Err(error("Cells lie outside of the size given in header"));
+                    }
+                    cells.extend((x..x + count).map(|x| ((x, y), state)));
+                    x += count;
+                }
+                '$' => {
+                    y += count;
+                    x = 0;
+                }
+                '!' => break 'lines,
+                c => return Err(error(&format!("Unexpected character '{}'", c))),
+            }
+        }
+    }
+    if size.is_none() {
+        return Err(ParseError::new(
+            line_count + 1,
+            1,
+            "Missing header line 'x = .., y = ..'",
+        ));
+    }
+
+    let (offset_x, offset_y) = offset.unwrap_or((0, 0));
+    pattern.shape = Shape::new(
+        cells
+            .iter()
+            .map(|&((x, y), _)| (x + offset_x, y + offset_y))
+            .collect(),
+    );
+    // States are kept relative to the shape like its points
+    let (pos_x, pos_y) = pattern.shape.pos();
+    pattern.states = cells
+        .into_iter()
+        .filter(|&(_, state)| state > 1)
+        .map(|((x, y), state)| ((x + offset_x - pos_x, y + offset_y - pos_y), state))
+        .collect();
+    Ok(pattern)
+}
+
+fn push_run(tokens: &mut Vec<String>, count: i32, tag: &str) {
+    match count {
+        0 => {}
+        1 => tokens.push(tag.to_string()),
+        _ => tokens.push(format!("{}{}", count, tag)),
+    }
+}
+
+// Tag of a state in the multi-state form, e.g. 'A' for 1 or 'pA' for 25
+fn state_tag(state: u8) -> String {
+    match state {
+        0 => ".".into(),
+        state => {
+            let prefix = (state - 1) / 24;
+            let letter = char::from(b'A' + (state - 1) % 24);
+            match prefix {
+                0 => letter.to_string(),
+                prefix => format!("{}{}", char::from(b'p' + prefix - 1), letter),
+            }
+        }
+    }
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out += &format!("#N {}\n", name);
+    }
+    if let Some(author) = &pattern.author {
+        out += &format!("#O {}\n", author);
+    }
+    for comment in &pattern.comments {
+        out += &format!("#C {}\n", comment);
+    }
+    let shape = &pattern.shape;
+    if shape.pos() != (0, 0) || pattern.generation.is_some() {
+        out += &format!("#CXRLE Pos={},{}", shape.pos().0, shape.pos().1);
+        if let Some(generation) = pattern.generation {
+            out += &format!(" Gen={}", generation);
+        }
+        out += "\n";
+    }
+    let (width, height) = shape.dimensions();
+    out += &format!("x = {}, y = {}", width, height);
+    let notation = pattern.rule.as_ref().and_then(|rule| rule.notation());
+    let grid = pattern.grid.as_ref().and_then(grid_notation);
+    match (notation, grid) {
+        (Some(notation), Some(grid)) => out += &format!(", rule = {}:{}", notation, grid),
+        (Some(notation), None) => out += &format!(", rule = {}", notation),
+        // Grid can't be given without a rule
+        (None, Some(grid)) => out += &format!(", rule = B3/S23:{}", grid),
+        (None, None) => {}
+    }
+    out += "\n";
+
+    // Patterns of rules with more than two states use letters for the states
+    let multi_state =
+        !pattern.states.is_empty() || pattern.rule.as_ref().is_some_and(|rule| rule.states() > 2);
+    let tag = |state: u8| match (multi_state, state) {
+        (true, state) => state_tag(state),
+        (false, 0) => "b".into(),
+        (false, _) => "o".into(),
+    };
+    let dead = tag(0);
+
+    let mut points = shape.points().to_vec();
+    points.sort_by_key(|&(x, y)| (y, x));
+    let mut tokens = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    // State and length of the run of cells ending at x
+    let mut cell_run = (0, 0);
+    for (point_x, point_y) in points {
+        let state = pattern.state((point_x, point_y));
+        if point_y != y || point_x != x || state != cell_run.0 {
+            push_run(&mut tokens, cell_run.1, &tag(cell_run.0));
+            cell_run = (state, 0);
+        }
+        if point_y != y {
+            push_run(&mut tokens, point_y - y, "$");
+            y = point_y;
+            x = 0;
+        }
+        push_run(&mut tokens, point_x - x, &dead);
+        cell_run.1 += 1;
+        x = point_x + 1;
+    }
+    push_run(&mut tokens, cell_run.1, &tag(cell_run.0));
+    tokens.push("!".into());
+
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length + token.len() > MAX_LINE_LENGTH {
+            out += "\n";
+            line_length = 0;
+        }
+        line_length += token.len();
+        out += &token;
+    }
+    out += "\n";
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: [(i32, i32); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    fn points(pattern: &Pattern) -> Vec<(i32, i32)> {
+        let (pos_x, pos_y) = pattern.shape.pos();
+        let mut points: Vec<_> = pattern
+            .shape
+            .points()
+            .iter()
+            .map(|&(x, y)| (x + pos_x, y + pos_y))
+            .collect();
+        points.sort_by_key(|&(x, y)| (y, x));
+        points
+    }
+
+    fn notation(pattern: &Pattern) -> Option<String> {
+        pattern.rule.as_ref().and_then(|rule| rule.notation())
+    }
+
+    #[test]
+    fn parses_glider() {
+        let pattern =
+            parse("#N Glider\n#O Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n")
+                .unwrap();
+        assert_eq!(points(&pattern), GLIDER);
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(notation(&pattern).as_deref(), Some("B3/S23"));
+    }
+
+    #[test]
+    fn whitespace_keeps_run_count() {
+        let pattern = parse("x = 3, y = 3\nbob$2 bo$3\no!\n").unwrap();
+        assert_eq!(points(&pattern), GLIDER);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(parse("bo!\n").unwrap_err().line, 1);
+        let error = parse("x = 3, y = 3\nbob$2bo$3z!\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 10));
+        assert!(parse("x = 2, y = 1\n3o!\n").is_err());
+        assert!(parse("x = 3, y = 3, rule = B3/S23:S30\n3o!\n").is_err());
+        assert!(parse("x = 3, y = 1\npb!\n").is_err());
+        assert!(parse("x = 3, y = 1\nyX!\n").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_runs() {
+        let error = parse("x = 2, y = 2\nbo0$o!\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        let error = parse("x = 2, y = 1\n\no0o!\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+        assert!(parse("x = 2, y = 1\n2o0!\n").is_err());
+        assert!(parse("x = 2, y = 1\n00b2o!\n").is_err());
+    }
+
+    #[test]
+    fn parses_states() {
+        let pattern = parse("x = 4, y = 2, rule = 345/2/4\n.A2B$pAC!\n").unwrap();
+        assert_eq!(points(&pattern), [(1, 0), (2, 0), (3, 0), (0, 1), (1, 1)]);
+        let states: Vec<_> = [(1, 0), (2, 0), (3, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|&point| pattern.state(point))
+            .collect();
+        assert_eq!(states, [1, 2, 2, 25, 3]);
+    }
+
+    #[test]
+    fn round_trip_states() {
+        let mut pattern = Pattern::new(Shape::new(vec![(0, 0), (1, 0), (2, 0), (5, 1)]));
+        pattern.states = vec![((1, 0), 2), ((2, 0), 255), ((5, 1), 24)]
+            .into_iter()
+            .collect();
+        let written = write(&pattern);
+        assert!(written.ends_with("AByO$5.X!\n"), "{}", written);
+        let read = parse(&written).unwrap();
+        assert_eq!(read.states, pattern.states);
+        assert_eq!(write(&read), written);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pattern = Pattern::new(Shape::new(GLIDER.to_vec()));
+        pattern.name = Some("Glider".into());
+        pattern.author = Some("Richard K. Guy".into());
+        pattern.comments = vec!["The smallest spaceship".into()];
+        pattern.rule = Some(rules::parse("B36/S23").unwrap());
+        let written = write(&pattern);
+        let read = parse(&written).unwrap();
+        assert_eq!(points(&read), GLIDER);
+        assert_eq!(read.name, pattern.name);
+        assert_eq!(read.author, pattern.author);
+        assert_eq!(read.comments, pattern.comments);
+        assert_eq!(notation(&read).as_deref(), Some("B36/S23"));
+        assert_eq!(write(&read), written);
+    }
+
+    #[test]
+    fn round_trip_extended_header() {
+        let mut pattern = Pattern::new(Shape::new(
+            GLIDER.iter().map(|&(x, y)| (x - 5, y + 7)).collect(),
+        ));
+        pattern.generation = Some(1234);
+        pattern.rule = Some(rules::parse("B3/S23").unwrap());
+        pattern.grid = Some(Grid {
+            topology: Topology::KleinBottle,
+            width: 30,
+            height: 20,
+        });
+        let written = write(&pattern);
+        assert!(written.starts_with("#CXRLE Pos=-5,7 Gen=1234\n"));
+        assert!(written.contains("rule = B3/S23:K30*,20"));
+        let read = parse(&written).unwrap();
+        assert_eq!(points(&read), points(&pattern));
+        assert_eq!(read.generation, Some(1234));
+        assert_eq!(read.grid, pattern.grid);
+        assert_eq!(write(&read), written);
+    }
+
+    #[test]
+    fn reads_golly_headers() {
+        let input = "#CXRLE Pos=-1,-1\nx = 3, y = 3, rule = B3/S23:T30,20\nbob$2bo$3o!\n";
+        let pattern = parse(input).unwrap();
+        assert_eq!(pattern.shape.pos(), (-1, -1));
+        assert_eq!(
+            pattern.grid,
+            Some(Grid {
+                topology: Topology::Torus,
+                width: 30,
+                height: 20
+            })
+        );
+        let input = "x = 3, y = 3, rule = R5,C0,M1,S34..58,B34..45,NM\nbob$2bo$3o!\n";
+        let pattern = parse(input).unwrap();
+        assert_eq!(
+            notation(&pattern).as_deref(),
+            Some("R5,C0,M1,S34..58,B34..45,NM")
+        );
+    }
+}
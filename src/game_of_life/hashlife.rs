@@ -0,0 +1,582 @@
+// Quadtree based universe stepped using Gosper's hashlife algorithm.
+// Identical subtrees are stored only once and results of their evolution
+// are memoized, which makes it possible to advance regular patterns
+// by huge numbers of generations at once.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::rules::LifeLike;
+use super::{Board, Topology};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+const MAX_LEVEL: u8 = 60;
+// Largest step of `step_pow2`, the root has to be at least 3 levels higher
+pub const MAX_STEP: u8 = MAX_LEVEL - 4;
+
+// Children of a node in order nw, ne, sw, se, level 0 nodes are single cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+}
+
+#[derive(Debug, Clone)]
+struct NodeData {
+    node: Node,
+    population: u128,
+}
+
+#[derive(Debug, Clone)]
+pub struct HashLife {
+    rule: LifeLike,
+    nodes: Vec<NodeData>,
+    index: HashMap<Node, NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    // Position of the top left corner of the root
+    origin: (i64, i64),
+    generation: u128,
+    memory_limit: Option<usize>,
+}
+
+impl HashLife {
+    // Returns None for rules with birth on zero neighbours,
+    // which would fill the whole unbounded universe
+    pub fn create(rule: LifeLike) -> Option<HashLife> {
+        if rule.birth().any(|count| count == 0) {
+            return None;
+        }
+        let leaf = |population| NodeData {
+            node: Node {
+                level: 0,
+                children: [DEAD; 4],
+            },
+            population,
+        };
+        let mut universe = HashLife {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (0, 0),
+            generation: 0,
+            memory_limit: None,
+        };
+        universe.root = universe.empty(3);
+        Some(universe)
+    }
+
+    // Returns None for boards with other topology than the plane, whose
+    // edges the unbounded universe can't follow
+    pub fn from_board(rule: LifeLike, board: &Board) -> Option<HashLife> {
+        if board.topology() != Topology::Plane {
+            return None;
+        }
+        let mut universe = HashLife::create(rule)?;
+        universe.read_board(board, (0, 0));
+        Some(universe)
+    }
+
+    pub fn rule(&self) -> &LifeLike {
+        &self.rule
+    }
+
+    pub fn generation(&self) -> u128 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u128 {
+        self.nodes[self.root as usize].population
+    }
+
+    // Approximate number of bytes used by the node cache
+    pub fn memory_usage(&self) -> usize {
+        let node_size = std::mem::size_of::<NodeData>()
+            + std::mem::size_of::<(Node, NodeId)>()
+            + std::mem::size_of::<u64>();
+        let result_size =
+            std::mem::size_of::<((NodeId, u8), NodeId)>() + std::mem::size_of::<u64>();
+        self.nodes.len() * node_size + self.results.len() * result_size
+    }
+
+    // Garbage is collected after a step whenever memory usage exceeds the limit
+    pub fn set_memory_limit(&mut self, bytes: Option<usize>) {
+        self.memory_limit = bytes;
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+}
+
+impl HashLife {
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].node.level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].node.children
+    }
+
+    fn node_population(&self, id: NodeId) -> u128 {
+        self.nodes[id as usize].population
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        let level = self.level(children[0]) + 1;
+        let node = Node { level, children };
+        if let Some(&id) = self.index.get(&node) {
+            return id;
+        }
+        let population = children
+            .iter()
+            .map(|&child| self.node_population(child))
+            .fold(0u128, u128::saturating_add);
+        let id = NodeId::try_from(self.nodes.len()).expect("Hashlife node cache is full");
+        self.nodes.push(NodeData { node, population });
+        self.index.insert(node, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= usize::from(level) {
+            let child = *self.empty.last().expect("Internal logic error");
+            let node = self.join([child; 4]);
+            self.empty.push(node);
+        }
+        self.empty[usize::from(level)]
+    }
+
+    // Middle part of the node, one level lower
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    fn horizontal(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let west = self.children(west);
+        let east = self.children(east);
+        self.join([west[1], east[0], west[3], east[2]])
+    }
+
+    fn vertical(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let north = self.children(north);
+        let south = self.children(south);
+        self.join([north[2], north[3], south[0], south[1]])
+    }
+
+    // Centre of level 2 node advanced by one generation
+    fn base_step(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (i, &child) in self.children(id).iter().enumerate() {
+            for (j, &leaf) in self.children(child).iter().enumerate() {
+                let x = (i % 2) * 2 + j % 2;
+                let y = (i / 2) * 2 + j / 2;
+                cells[y][x] = leaf == ALIVE;
+            }
+        }
+        let next = |x: usize, y: usize| {
+            let mut neighbours_count = 0;
+            for row in &cells[y - 1..=y + 1] {
+                neighbours_count += row[x - 1..=x + 1].iter().filter(|&&cell| cell).count();
+            }
+            if cells[y][x] {
+                neighbours_count -= 1;
+            }
+            if self.rule.next_state(cells[y][x], neighbours_count as u8) {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let result = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(result)
+    }
+
+    // Centre of the node advanced by 2^step generations, step <= level - 2
+    fn successor(&mut self, id: NodeId, step: u8) -> NodeId {
+        let level = self.level(id);
+        assert!(level >= 2 && step <= level - 2);
+        if self.node_population(id) == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.base_step(id)
+        } else {
+            let [nw, ne, sw, se] = self.children(id);
+            let parts = [
+                nw,
+                self.horizontal(nw, ne),
+                ne,
+                self.vertical(nw, sw),
+                self.centre(id),
+                self.vertical(ne, se),
+                sw,
+                self.horizontal(sw, se),
+                se,
+            ];
+            // At full speed both halves of the step are done in recursion,
+            // otherwise only the second one
+            let full_speed = step == level - 2;
+            let mut reduced = [DEAD; 9];
+            for (reduced, &part) in reduced.iter_mut().zip(parts.iter()) {
+                *reduced = if full_speed {
+                    self.successor(part, step - 1)
+                } else {
+                    self.centre(part)
+                };
+            }
+            let step = if full_speed { step - 1 } else { step };
+            let r = reduced;
+            let quarters = [
+                [r[0], r[1], r[3], r[4]],
+                [r[1], r[2], r[4], r[5]],
+                [r[3], r[4], r[6], r[7]],
+                [r[4], r[5], r[7], r[8]],
+            ];
+            let mut result = [DEAD; 4];
+            for (result, &quarter) in result.iter_mut().zip(quarters.iter()) {
+                let quarter = self.join(quarter);
+                *result = self.successor(quarter, step);
+            }
+            self.join(result)
+        };
+        self.results.insert((id, step), result);
+        result
+    }
+
+    // Doubles the size of the root keeping it centred, fails once the root
+    // would be larger than `MAX_LEVEL`
+    fn expand(&mut self) -> Option<()> {
+        let level = self.level(self.root);
+        if level >= MAX_LEVEL {
+            return None;
+        }
+        let empty = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(self.root);
+        let children = [
+            self.join([empty, empty, empty, nw]),
+            self.join([empty, empty, ne, empty]),
+            self.join([empty, sw, empty, empty]),
+            self.join([se, empty, empty, empty]),
+        ];
+        self.root = self.join(children);
+        let shift = 1i64 << (level - 1);
+        self.origin = (self.origin.0 - shift, self.origin.1 - shift);
+        Some(())
+    }
+
+    // Whether the whole population lies in the middle quarter of the root
+    fn is_padded(&mut self) -> bool {
+        if self.level(self.root) < 3 {
+            return false;
+        }
+        let centre = self.centre(self.root);
+        let centre = self.centre(centre);
+        self.node_population(centre) == self.population()
+    }
+
+    fn size(&self) -> i64 {
+        1 << self.level(self.root)
+    }
+
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        let size = self.size();
+        (self.origin.0..self.origin.0 + size).contains(&x)
+            && (self.origin.1..self.origin.1 + size).contains(&y)
+    }
+}
+
+impl HashLife {
+    // Advances the universe by 2^step generations, fails if `step` is
+    // larger than `MAX_STEP` or if the pattern has spread so far that the
+    // root can't hold the result
+    pub fn step_pow2(&mut self, step: u8) -> Option<()> {
+        if step > MAX_STEP {
+            return None;
+        }
+        while self.level(self.root) < step + 3 || !self.is_padded() {
+            self.expand()?;
+        }
+        let level = self.level(self.root);
+        self.root = self.successor(self.root, step);
+        let shift = 1i64 << (level - 2);
+        self.origin = (self.origin.0 + shift, self.origin.1 + shift);
+        self.generation = self.generation.saturating_add(1 << step);
+
+        if let Some(limit) = self.memory_limit {
+            if self.memory_usage() > limit {
+                self.collect_garbage();
+            }
+        }
+        Some(())
+    }
+
+    // Drops memoized results and every node not reachable from the root
+    pub fn collect_garbage(&mut self) {
+        let mut nodes = Vec::with_capacity(self.nodes.len() / 2);
+        nodes.extend_from_slice(&self.nodes[..2]);
+        let mut universe = HashLife {
+            rule: self.rule.clone(),
+            nodes,
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: self.origin,
+            generation: self.generation,
+            memory_limit: self.memory_limit,
+        };
+        let mut mapping = HashMap::new();
+        universe.root = universe.copy_node(self, self.root, &mut mapping);
+        *self = universe;
+    }
+
+    fn copy_node(
+        &mut self,
+        from: &HashLife,
+        id: NodeId,
+        mapping: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if from.level(id) == 0 {
+            return id;
+        }
+        if let Some(&new_id) = mapping.get(&id) {
+            return new_id;
+        }
+        let mut children = from.children(id);
+        for child in &mut children {
+            *child = self.copy_node(from, *child, mapping);
+        }
+        let new_id = self.join(children);
+        mapping.insert(id, new_id);
+        new_id
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        if !self.contains((x, y)) {
+            return false;
+        }
+        let mut id = self.root;
+        let (mut x, mut y) = (x - self.origin.0, y - self.origin.1);
+        while self.level(id) > 0 {
+            let half = 1 << (self.level(id) - 1);
+            let quadrant = usize::from(x >= half) + 2 * usize::from(y >= half);
+            id = self.children(id)[quadrant];
+            x %= half;
+            y %= half;
+        }
+        id == ALIVE
+    }
+
+    // Fails for positions too far from the live cells for the root to hold both
+    pub fn set(&mut self, x: i64, y: i64, value: bool) -> Option<()> {
+        if self.get(x, y) == value {
+            return Some(());
+        }
+        while !self.contains((x, y)) {
+            self.expand()?;
+        }
+        let (x, y) = (x - self.origin.0, y - self.origin.1);
+        self.root = self.set_in(self.root, (x, y), value);
+        Some(())
+    }
+
+    fn set_in(&mut self, id: NodeId, (x, y): (i64, i64), value: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if value { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let quadrant = usize::from(x >= half) + 2 * usize::from(y >= half);
+        let mut children = self.children(id);
+        children[quadrant] = self.set_in(children[quadrant], (x % half, y % half), value);
+        self.join(children)
+    }
+
+    // Calls `f` for every live cell in rectangle [from, to)
+    pub fn for_each_alive<F>(&self, from: (i64, i64), to: (i64, i64), mut f: F)
+    where
+        F: FnMut(i64, i64),
+    {
+        self.for_each_alive_in(self.root, self.origin, (from, to), &mut f);
+    }
+
+    fn for_each_alive_in<F>(
+        &self,
+        id: NodeId,
+        pos: (i64, i64),
+        rect: ((i64, i64), (i64, i64)),
+        f: &mut F,
+    ) where
+        F: FnMut(i64, i64),
+    {
+        let ((x_from, y_from), (x_to, y_to)) = rect;
+        let size = 1 << self.level(id);
+        if self.node_population(id) == 0
+            || pos.0 >= x_to
+            || pos.1 >= y_to
+            || pos.0 + size <= x_from
+            || pos.1 + size <= y_from
+        {
+            return;
+        }
+        if size == 1 {
+            f(pos.0, pos.1);
+            return;
+        }
+        let half = size / 2;
+        for (i, &child) in self.children(id).iter().enumerate() {
+            let x = pos.0 + half * (i % 2) as i64;
+            let y = pos.1 + half * (i / 2) as i64;
+            self.for_each_alive_in(child, (x, y), rect, f);
+        }
+    }
+
+    // Sets cells of the universe from board placed at `pos`
+    pub fn read_board(&mut self, board: &Board, pos: (i64, i64)) {
+        for (&cell, (x, y)) in board.indexed_iter() {
+            self.set(pos.0 + i64::from(x), pos.1 + i64::from(y), cell);
+        }
+    }
+
+    // Copies region of the universe starting at `pos` into the board
+    pub fn write_board(&self, board: &mut Board, pos: (i64, i64)) {
+        board.clear();
+        let to = (pos.0 + board.width() as i64, pos.1 + board.height() as i64);
+        self.for_each_alive(pos, to, |x, y| {
+            board.set(x - pos.0, y - pos.1, true);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::rules::Rule;
+    use crate::game_of_life::SparseBoard;
+
+    // R-pentomino, which keeps growing for over a thousand generations
+    const PATTERN: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+    fn alive(universe: &HashLife) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        let limit = 1 << 20;
+        universe.for_each_alive((-limit, -limit), (limit, limit), |x, y| cells.push((x, y)));
+        cells.sort_unstable();
+        cells
+    }
+
+    fn stepped(generations: u64) -> Vec<(i64, i64)> {
+        let mut board = SparseBoard::create();
+        for &(x, y) in &PATTERN {
+            board.set(x, y, true);
+        }
+        let mut rule = LifeLike::conway();
+        for _ in 0..generations {
+            board = rule.next_sparse_board(&board).unwrap();
+        }
+        let mut cells: Vec<_> = board.indexed_iter().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn universe() -> HashLife {
+        let mut universe = HashLife::create(LifeLike::conway()).unwrap();
+        for &(x, y) in &PATTERN {
+            universe.set(x, y, true);
+        }
+        universe
+    }
+
+    #[test]
+    fn set_and_get() {
+        let mut universe = HashLife::create(LifeLike::conway()).unwrap();
+        universe.set(-100, 37, true);
+        universe.set(5, 5, true);
+        universe.set(5, 5, false);
+        assert!(universe.get(-100, 37));
+        assert!(!universe.get(5, 5));
+        assert_eq!(universe.population(), 1);
+        assert_eq!(alive(&universe), vec![(-100, 37)]);
+    }
+
+    #[test]
+    fn jumps_match_single_steps() {
+        for step in 0..7 {
+            let mut universe = universe();
+            universe.step_pow2(step).unwrap();
+            assert_eq!(universe.generation(), 1 << step);
+            assert_eq!(alive(&universe), stepped(1 << step), "step {}", step);
+        }
+    }
+
+    #[test]
+    fn consecutive_jumps() {
+        let mut universe = universe();
+        for &step in &[3, 0, 5, 2] {
+            universe.step_pow2(step).unwrap();
+        }
+        assert_eq!(alive(&universe), stepped(8 + 1 + 32 + 4));
+    }
+
+    #[test]
+    fn garbage_collection_keeps_universe() {
+        let mut universe = universe();
+        universe.set_memory_limit(Some(0));
+        universe.step_pow2(4).unwrap();
+        universe.step_pow2(4).unwrap();
+        assert_eq!(alive(&universe), stepped(32));
+    }
+
+    #[test]
+    fn rejects_too_large_steps() {
+        let mut universe = universe();
+        assert_eq!(universe.step_pow2(MAX_STEP + 1), None);
+        assert_eq!(universe.step_pow2(u8::MAX), None);
+        assert_eq!(universe.generation(), 0);
+    }
+
+    #[test]
+    fn rejects_growing_past_max_level() {
+        // Glider, which moves one cell diagonally every 4 generations
+        let mut universe = HashLife::create(LifeLike::conway()).unwrap();
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            universe.set(x, y, true);
+        }
+        let jumps = (0..100)
+            .take_while(|_| universe.step_pow2(MAX_STEP).is_some())
+            .count();
+        assert!(jumps > 0 && jumps < 100, "{}", jumps);
+        assert_eq!(universe.population(), 5);
+        assert_eq!(universe.generation(), (jumps as u128) << MAX_STEP);
+
+        let mut universe = HashLife::create(LifeLike::conway()).unwrap();
+        assert_eq!(universe.set(i64::MAX, 0, true), None);
+        assert_eq!(universe.set(i64::MIN, 0, false), Some(()));
+    }
+
+    #[test]
+    fn rejects_bounded_topologies() {
+        let board = Board::create(8, 8).with_topology(Topology::Torus);
+        assert!(HashLife::from_board(LifeLike::conway(), &board).is_none());
+        let board = board.with_topology(Topology::Plane);
+        assert!(HashLife::from_board(LifeLike::conway(), &board).is_some());
+    }
+}
@@ -0,0 +1,239 @@
+// Bounded record of past states of the game, states can be undone and
+// redone until a new state is pushed, which drops the undone ones
+
+use std::collections::VecDeque;
+
+use super::Board;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub board: Board,
+    pub generation: u64,
+}
+
+impl State {
+    fn memory_usage(&self) -> usize {
+        let states = self.board.states.as_ref().map_or(0, Vec::len);
+        std::mem::size_of_val(&self.board.words[..]) + states
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    states: VecDeque<State>,
+    // States before the position are in the past, the rest were undone
+    position: usize,
+    capacity: usize,
+    memory_limit: Option<usize>,
+    // Sum of `State::memory_usage` of all the states
+    memory_usage: usize,
+}
+
+impl History {
+    // Keeps at most `capacity` states, older ones are dropped
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            states: VecDeque::new(),
+            position: 0,
+            capacity,
+            memory_limit: None,
+            memory_usage: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.shrink();
+    }
+
+    // Approximate number of bytes taken by the boards
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+        self.shrink();
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+        self.position = 0;
+        self.memory_usage = 0;
+    }
+
+    // Number of states which can be undone
+    pub fn undo_count(&self) -> usize {
+        self.position
+    }
+
+    // Number of states which can be redone
+    pub fn redo_count(&self) -> usize {
+        self.states.len() - self.position
+    }
+
+    // Records state which is being left, undone states are dropped
+    pub fn push(&mut self, state: State) {
+        for undone in self.states.drain(self.position..) {
+            self.memory_usage -= undone.memory_usage();
+        }
+        self.memory_usage += state.memory_usage();
+        self.states.push_back(state);
+        self.position = self.states.len();
+        self.shrink();
+    }
+
+    // Swaps the current state with the previous one
+    pub fn undo(&mut self, current: &mut State) -> Option<()> {
+        self.position = self.position.checked_sub(1)?;
+        self.swap(self.position, current);
+        Some(())
+    }
+
+    // Swaps the current state with the next one
+    pub fn redo(&mut self, current: &mut State) -> Option<()> {
+        if self.position == self.states.len() {
+            return None;
+        }
+        self.swap(self.position, current);
+        self.position += 1;
+        Some(())
+    }
+
+    fn swap(&mut self, index: usize, current: &mut State) {
+        let state = &mut self.states[index];
+        self.memory_usage = self.memory_usage - state.memory_usage() + current.memory_usage();
+        std::mem::swap(state, current);
+    }
+
+    // Drops the oldest states until the limits are met, once every state
+    // was undone the ones furthest in the future go first
+    fn shrink(&mut self) {
+        while self.states.len() > self.capacity
+            || self
+                .memory_limit
+                .is_some_and(|limit| self.memory_usage > limit)
+        {
+            let state = if self.position > 0 {
+                self.position -= 1;
+                self.states.pop_front()
+            } else {
+                self.states.pop_back()
+            };
+            match state {
+                Some(state) => self.memory_usage -= state.memory_usage(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(generation: u64) -> State {
+        State {
+            board: Board::create(64, 2),
+            generation,
+        }
+    }
+
+    fn generations(history: &History) -> Vec<u64> {
+        history
+            .states
+            .iter()
+            .map(|state| state.generation)
+            .collect()
+    }
+
+    fn recomputed_memory_usage(history: &History) -> usize {
+        history.states.iter().map(State::memory_usage).sum()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new(10);
+        let mut current = state(0);
+        assert_eq!(history.undo(&mut current), None);
+        for generation in 0..3 {
+            history.push(state(generation));
+        }
+        current = state(3);
+        history.undo(&mut current).unwrap();
+        history.undo(&mut current).unwrap();
+        assert_eq!(current.generation, 1);
+        assert_eq!((history.undo_count(), history.redo_count()), (1, 2));
+        history.redo(&mut current).unwrap();
+        assert_eq!(current.generation, 2);
+        assert_eq!(generations(&history), [0, 1, 3]);
+
+        // New state drops the undone ones
+        history.push(state(7));
+        assert_eq!(history.redo(&mut current), None);
+        assert_eq!(generations(&history), [0, 1, 7]);
+        assert_eq!(history.memory_usage(), recomputed_memory_usage(&history));
+    }
+
+    #[test]
+    fn drops_oldest_states() {
+        let mut history = History::new(3);
+        for generation in 0..5 {
+            history.push(state(generation));
+        }
+        assert_eq!(generations(&history), [2, 3, 4]);
+        history.set_capacity(2);
+        assert_eq!(generations(&history), [3, 4]);
+        assert_eq!(history.undo_count(), 2);
+
+        let mut current = state(5);
+        history.undo(&mut current).unwrap();
+        history.set_capacity(1);
+        assert_eq!(generations(&history), [5]);
+        assert_eq!((history.undo_count(), history.redo_count()), (0, 1));
+
+        // Redo has to give the next state, not a later one
+        let mut history = History::new(3);
+        for generation in 0..3 {
+            history.push(state(generation));
+        }
+        current = state(3);
+        for _ in 0..3 {
+            history.undo(&mut current).unwrap();
+        }
+        history.set_capacity(2);
+        assert_eq!(generations(&history), [1, 2]);
+        history.redo(&mut current).unwrap();
+        assert_eq!(current.generation, 1);
+    }
+
+    #[test]
+    fn respects_memory_limit() {
+        let mut history = History::new(100);
+        let size = state(0).memory_usage();
+        history.set_memory_limit(Some(3 * size));
+        for generation in 0..5 {
+            history.push(state(generation));
+        }
+        assert_eq!(generations(&history), [2, 3, 4]);
+        assert_eq!(history.memory_usage(), 3 * size);
+
+        // Larger boards take more memory
+        history.push(State {
+            board: Board::create(128, 2),
+            generation: 5,
+        });
+        assert_eq!(generations(&history), [4, 5]);
+        assert_eq!(history.memory_usage(), recomputed_memory_usage(&history));
+        history.clear();
+        assert_eq!(history.memory_usage(), 0);
+    }
+}
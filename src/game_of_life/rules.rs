@@ -1,5 +1,130 @@
+mod bitwise;
+
+pub mod life_like;
+pub use life_like::{LifeLike, ParseRuleError};
+
+pub mod generations;
+pub use generations::Generations;
+
+pub mod neighbourhood;
+pub use neighbourhood::Neighbourhood;
+
+pub mod larger_than_life;
+pub use larger_than_life::LargerThanLife;
+
+pub mod outer_totalistic;
+pub use outer_totalistic::OuterTotalistic;
+
+pub mod isotropic;
+pub use isotropic::Isotropic;
+
+pub mod table;
+pub use table::RuleTable;
+
+// Creates rule from its rule string, e.g. "B36/S23", "B2/S34H", "B2-a/S12",
+// "345/2/4" or "R5,C0,M1,S34..58,B34..45,NM", or loads it from a Golly
+// .rule file, given by its path, e.g. "rules/WireWorld.rule", or by the
+// name of a table in the rules directories, e.g. "WireWorld"
+pub fn parse(rule: &str) -> Result<Box<dyn Rule>, ParseRuleError> {
+    if rule.trim().to_ascii_lowercase().ends_with(".rule") {
+        let path = std::path::Path::new(rule.trim());
+        return Ok(Box::new(RuleTable::load(path)?));
+    }
+    let error = match parse_rule_string(rule) {
+        Ok(rule) => return Ok(rule),
+        Err(error) => error,
+    };
+    // Names of tables can't be told apart from invalid rule strings, so they
+    // are looked up only once the rule string fails
+    let name = rule.trim();
+    if name.is_empty() || name.contains(|c: char| "/\\,.".contains(c) || c.is_whitespace()) {
+        return Err(error);
+    }
+    match RuleTable::find(name) {
+        Some(path) => Ok(Box::new(RuleTable::load(&path)?)),
+        None => {
+            let directories: Vec<_> = RuleTable::directories()
+                .iter()
+                .map(|directory| directory.display().to_string())
+                .collect();
+            Err(ParseRuleError::InvalidTable(format!(
+                "{}, and there is no '{}.rule' in {}",
+                error,
+                name,
+                directories.join(", ")
+            )))
+        }
+    }
+}
+
+fn parse_rule_string(rule: &str) -> Result<Box<dyn Rule>, ParseRuleError> {
+    if rule.contains(',') {
+        return Ok(Box::new(rule.parse::<LargerThanLife>()?));
+    }
+    if rule.trim_end().ends_with(|c: char| "HhVv".contains(c)) {
+        return Ok(Box::new(rule.parse::<OuterTotalistic>()?));
+    }
+    // Third section gives the number of states, e.g. "B2/S345/C4", whose
+    // letter would be taken for a Hensel letter
+    if rule.matches('/').count() == 2 {
+        return Ok(Box::new(rule.parse::<Generations>()?));
+    }
+    if rule.contains(|c: char| c == '-' || (c.is_ascii_alphabetic() && !"BbSs".contains(c))) {
+        return Ok(Box::new(rule.parse::<Isotropic>()?));
+    }
+    Ok(Box::new(rule.parse::<LifeLike>()?))
+}
+
 pub trait Rule {
     fn next_board(&mut self, board: &super::Board) -> super::Board;
+
+    // Has to give the same result as `next_board`, rules which don't support
+    // parallel stepping fall back to it
+    fn next_board_parallel(
+        &mut self,
+        board: &super::Board,
+        pool: &super::ThreadPool,
+    ) -> super::Board {
+        let _ = pool;
+        self.next_board(board)
+    }
+
+    // Rules seeing further than the direct neighbours have to override this,
+    // fails for rules with more than two states, as sparse boards hold only
+    // live cells
+    fn next_sparse_board(&mut self, board: &super::SparseBoard) -> Option<super::SparseBoard> {
+        if self.states() > 2 {
+            return None;
+        }
+        Some(board.step_tiles(|tile| self.next_board(tile)))
+    }
+
+    // Rule string in the notation accepted by `parse`, if there is one
+    fn notation(&self) -> Option<String> {
+        None
+    }
+
+    // Rules which can be expressed in B/S notation can be run by hashlife
+    fn life_like(&self) -> Option<LifeLike> {
+        None
+    }
+
+    // Number of cell states including dead and alive, see `Board::get_state`
+    fn states(&self) -> u8 {
+        2
+    }
+
+    // Rules on hexagonal grids, see `Neighbourhood::Hexagonal`, are shown
+    // with rows shifted by half a cell
+    fn hexagonal(&self) -> bool {
+        false
+    }
+
+    // Color of the state given by the rule, None for the default colors
+    fn state_color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        let _ = state;
+        None
+    }
 }
 
 pub trait BasicRule {
@@ -8,13 +133,46 @@ pub trait BasicRule {
 
 impl<T: BasicRule> Rule for T {
     fn next_board(&mut self, board: &super::Board) -> super::Board {
-        assert_eq!(board.cells.len() % board.height, 0);
-        let rule = |(cell, pos)| Self::rule(board, cell, pos);
-        super::Board {
-            height: board.height,
-            cells: board.indexed_iter().map(rule).collect(),
-        }
+        board.map(|cell, pos| Self::rule(board, cell, pos))
     }
+
+    fn next_board_parallel(
+        &mut self,
+        board: &super::Board,
+        pool: &super::ThreadPool,
+    ) -> super::Board {
+        let words_per_row = board.words_per_row();
+        next_board_by_rows(board, pool, |rows, out| {
+            let from = rows.start;
+            for y in rows {
+                for x in 0..board.width() {
+                    let pos = (x as i32, y as i32);
+                    let cell = board.get(x, y).expect("Internal logic error");
+                    if Self::rule(board, cell, pos) {
+                        let index = (y - from) * words_per_row + x / super::board::WORD_BITS;
+                        out[index] |= 1 << (x % super::board::WORD_BITS);
+                    }
+                }
+            }
+        })
+    }
+}
+
+// Splits rows of the next board into bands, each computed by `next_rows`
+// on a thread of the pool
+fn next_board_by_rows<F>(
+    board: &super::Board,
+    pool: &super::ThreadPool,
+    next_rows: F,
+) -> super::Board
+where
+    F: Fn(std::ops::Range<usize>, &mut [u64]) + Sync,
+{
+    let mut next =
+        super::Board::create(board.width(), board.height()).with_topology(board.topology);
+    let words_per_row = board.words_per_row();
+    pool.for_each_band(&mut next.words, words_per_row, next_rows);
+    next
 }
 
 #[derive(Debug, Default)]
@@ -29,30 +187,33 @@ impl Rule for Empty {
 #[derive(Debug, Default)]
 pub struct Conway {}
 
-impl BasicRule for Conway {
-    fn rule(board: &super::Board, cell: &bool, pos: (i32, i32)) -> bool {
-        let neighbours_count = get_neighbours_count::<false>(&board, pos);
-        matches!((cell, neighbours_count), (true, 2..=3) | (false, 3))
+impl Rule for Conway {
+    fn next_board(&mut self, board: &super::Board) -> super::Board {
+        bitwise::next_board(board, &LifeLike::conway())
     }
-}
 
-#[derive(Debug, Default)]
-pub struct ConwayWrapped {}
+    fn next_board_parallel(
+        &mut self,
+        board: &super::Board,
+        pool: &super::ThreadPool,
+    ) -> super::Board {
+        bitwise::next_board_parallel(board, &LifeLike::conway(), pool)
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(LifeLike::conway().to_string())
+    }
 
-impl BasicRule for ConwayWrapped {
-    fn rule(board: &super::Board, cell: &bool, pos: (i32, i32)) -> bool {
-        let neighbours_count = get_neighbours_count::<true>(&board, pos);
-        matches!((cell, neighbours_count), (true, 2..=3) | (false, 3))
+    fn life_like(&self) -> Option<LifeLike> {
+        Some(LifeLike::conway())
     }
 }
 
-fn get_neighbours_count<const WRAPPED: bool>(board: &super::Board, (x, y): (i32, i32)) -> u8 {
-    let width = board.width() as i32;
-    let height = board.height() as i32;
+fn get_neighbours_count(board: &super::Board, (x, y): (i32, i32)) -> u8 {
     assert!(x >= 0);
     assert!(y >= 0);
-    assert!(x < width);
-    assert!(y < height);
+    assert!(x < board.width() as i32);
+    assert!(y < board.height() as i32);
 
     let mut result = 0;
     for &i in &[-1, 0, 1] {
@@ -60,19 +221,113 @@ fn get_neighbours_count<const WRAPPED: bool>(board: &super::Board, (x, y): (i32,
             if (i, j) == (0, 0) {
                 continue;
             }
-            let &cell = if WRAPPED {
-                let x = (x + i + width) % width;
-                let y = (y + j + height) % height;
-                board.get(x, y).expect("Internal logic error")
-            } else {
-                let x = x + i;
-                let y = y + j;
-                board.get(x, y).unwrap_or(&false)
-            };
-            if cell {
+            if board.get_wrapped(x + i, y + j) {
                 result += 1;
             }
         }
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::{Board, ThreadPool, Topology};
+
+    // Cells copy their west neighbour on the plane
+    struct Shift {}
+
+    impl BasicRule for Shift {
+        fn rule(board: &Board, _: &bool, (x, y): (i32, i32)) -> bool {
+            board.get(x - 1, y) == Some(&true)
+        }
+    }
+
+    #[test]
+    fn parallel_steps_match_sequential() {
+        let wireworld =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("rules/WireWorld.rule");
+        let rules: Vec<Box<dyn Rule>> = vec![
+            Box::new(Conway {}),
+            Box::new(Shift {}),
+            Box::new("B36/S23".parse::<LifeLike>().unwrap()),
+            Box::new("345/2/4".parse::<Generations>().unwrap()),
+            Box::new("R2,C3,M0,S2..5,B3..4,NM".parse::<LargerThanLife>().unwrap()),
+            Box::new("B2/S34H".parse::<OuterTotalistic>().unwrap()),
+            Box::new("B2c3aeijr/S23-k".parse::<Isotropic>().unwrap()),
+            Box::new(RuleTable::load(&wireworld).unwrap()),
+        ];
+        let pools: Vec<_> = (2..=5).map(ThreadPool::create).collect();
+        for mut rule in rules {
+            for &(width, height) in &[(1, 1), (70, 1), (5, 3), (67, 7), (33, 13)] {
+                let mut board = Board::create(width, height).with_topology(Topology::Torus);
+                board.randomize_seeded(height as u64, 0.4);
+                for generation in 0..3 {
+                    let next = rule.next_board(&board);
+                    for pool in &pools {
+                        assert_eq!(
+                            rule.next_board_parallel(&board, pool),
+                            next,
+                            "{:?} {}x{} generation {} with {} threads",
+                            rule.notation(),
+                            width,
+                            height,
+                            generation,
+                            pool.threads()
+                        );
+                    }
+                    board = next;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_own_notation() {
+        let wireworld =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("rules/WireWorld.rule");
+        let rules: Vec<Box<dyn Rule>> = vec![
+            Box::new(Conway {}),
+            Box::new("B36/S23".parse::<LifeLike>().unwrap()),
+            Box::new("B0/S".parse::<LifeLike>().unwrap()),
+            Box::new("345/2/4".parse::<Generations>().unwrap()),
+            Box::new("B2/S/C3".parse::<Generations>().unwrap()),
+            Box::new("B2/S345/G25".parse::<Generations>().unwrap()),
+            Box::new(
+                "R5,C0,M1,S34..58,B34..45,NM"
+                    .parse::<LargerThanLife>()
+                    .unwrap(),
+            ),
+            Box::new("R2,C3,M0,S2..3,B3..3,NH".parse::<LargerThanLife>().unwrap()),
+            Box::new("B2/S34H".parse::<OuterTotalistic>().unwrap()),
+            Box::new("B2/S013V".parse::<OuterTotalistic>().unwrap()),
+            Box::new("B2-a/S12".parse::<Isotropic>().unwrap()),
+            Box::new("B2c3aeijr/S23-k".parse::<Isotropic>().unwrap()),
+            Box::new(RuleTable::load(&wireworld).unwrap()),
+        ];
+        for rule in rules {
+            let notation = rule.notation().unwrap();
+            let parsed = parse(&notation).unwrap_or_else(|e| panic!("{}: {}", notation, e));
+            assert_eq!(parsed.notation().as_deref(), Some(notation.as_str()));
+            assert_eq!(parsed.states(), rule.states(), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn finds_tables_by_name() {
+        let wireworld = parse("WireWorld").unwrap();
+        assert_eq!(wireworld.states(), 4);
+        assert_eq!(wireworld.notation().as_deref(), Some("WireWorld"));
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("rules/WireWorld.rule");
+        let loaded = parse(&path.display().to_string()).unwrap();
+        assert_eq!(loaded.notation().as_deref(), Some("WireWorld"));
+
+        let missing = parse("NoSuchRule").err().unwrap().to_string();
+        assert!(missing.contains("NoSuchRule.rule"), "{}", missing);
+        // Invalid rule strings aren't taken for names
+        assert_eq!(
+            parse("B3/S2x").err(),
+            Some(ParseRuleError::InvalidLetter('2', 'x'))
+        );
+    }
+}
@@ -0,0 +1,224 @@
+// Word-parallel stepping of life-like rules,
+// neighbour counts of 64 cells are computed at once with bitwise adders
+
+use super::LifeLike;
+use crate::game_of_life::{Board, ThreadPool, Topology};
+
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let (sum, carry_1) = half_add(a, b);
+    let (sum, carry_2) = half_add(sum, c);
+    (sum, carry_1 | carry_2)
+}
+
+// Masks of neighbour counts for which the cell is born/survives,
+// count `n` is encoded as bits `n & 1`, `n & 2`, `n & 4` and `n & 8`
+struct CountMasks {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl CountMasks {
+    fn new(rule: &LifeLike) -> Self {
+        let mut masks = CountMasks {
+            birth: [false; 9],
+            survival: [false; 9],
+        };
+        for count in rule.birth() {
+            masks.birth[usize::from(count)] = true;
+        }
+        for count in rule.survival() {
+            masks.survival[usize::from(count)] = true;
+        }
+        masks
+    }
+
+    fn apply(&self, cells: u64, count: [u64; 4]) -> u64 {
+        let mut birth = 0;
+        let mut survival = 0;
+        for n in 0..9 {
+            if !self.birth[n] && !self.survival[n] {
+                continue;
+            }
+            let equal = (0..4)
+                .map(|bit| {
+                    if n & (1 << bit) != 0 {
+                        count[bit]
+                    } else {
+                        !count[bit]
+                    }
+                })
+                .fold(!0, |acc, mask| acc & mask);
+            if self.birth[n] {
+                birth |= equal;
+            }
+            if self.survival[n] {
+                survival |= equal;
+            }
+        }
+        (cells & survival) | (!cells & birth)
+    }
+}
+
+// Word of the row shifted so that each bit holds its west/east neighbour
+fn west(row: &[u64], i: usize) -> u64 {
+    let carry = if i > 0 { row[i - 1] >> 63 } else { 0 };
+    (row[i] << 1) | carry
+}
+
+fn east(row: &[u64], i: usize) -> u64 {
+    let carry = row.get(i + 1).map_or(0, |word| word << 63);
+    (row[i] >> 1) | carry
+}
+
+// Computes rows `rows` of the next generation into `out`, treating cells
+// outside of the board as dead
+pub(super) fn next_rows(
+    board: &Board,
+    rule: &LifeLike,
+    rows: std::ops::Range<usize>,
+    out: &mut [u64],
+) {
+    let words_per_row = board.words_per_row();
+    assert_eq!(out.len(), rows.len() * words_per_row);
+    if words_per_row == 0 {
+        return;
+    }
+    let masks = CountMasks::new(rule);
+    let empty = vec![0; words_per_row];
+    let last_word_mask = board.last_word_mask();
+
+    for (y, out_row) in rows.zip(out.chunks_exact_mut(words_per_row)) {
+        let above = if y > 0 { board.row(y - 1) } else { &empty };
+        let below = if y + 1 < board.height {
+            board.row(y + 1)
+        } else {
+            &empty
+        };
+        let current = board.row(y);
+
+        for (i, out_word) in out_row.iter_mut().enumerate() {
+            let (above_1, above_2) = full_add(west(above, i), above[i], east(above, i));
+            let (below_1, below_2) = full_add(west(below, i), below[i], east(below, i));
+            let (current_1, current_2) = half_add(west(current, i), east(current, i));
+
+            let (bit_1, carry_2) = full_add(above_1, below_1, current_1);
+            let (twos, carry_4) = full_add(above_2, below_2, current_2);
+            let (bit_2, carry_4_2) = half_add(twos, carry_2);
+            let (bit_4, bit_8) = half_add(carry_4, carry_4_2);
+
+            *out_word = masks.apply(current[i], [bit_1, bit_2, bit_4, bit_8]);
+        }
+        out_row[words_per_row - 1] &= last_word_mask;
+    }
+}
+
+// Recomputes cells on the edges of the board, which see across the edge
+// on surfaces other than plane
+pub(super) fn fix_edges(board: &Board, rule: &LifeLike, next: &mut Board) {
+    if board.topology == Topology::Plane || board.width == 0 || board.height == 0 {
+        return;
+    }
+    let width = board.width as i32;
+    let height = board.height as i32;
+    let mut fix = |x: i32, y: i32| {
+        let cell = *board.get(x, y).expect("Internal logic error");
+        let neighbours_count = super::get_neighbours_count(board, (x, y));
+        next.set(x, y, rule.next_state(cell, neighbours_count));
+    };
+    for x in 0..width {
+        fix(x, 0);
+        fix(x, height - 1);
+    }
+    for y in 0..height {
+        fix(0, y);
+        fix(width - 1, y);
+    }
+}
+
+pub(super) fn next_board(board: &Board, rule: &LifeLike) -> Board {
+    let mut next = Board::create(board.width, board.height).with_topology(board.topology);
+    next_rows(board, rule, 0..board.height, &mut next.words);
+    fix_edges(board, rule, &mut next);
+    next
+}
+
+pub(super) fn next_board_parallel(board: &Board, rule: &LifeLike, pool: &ThreadPool) -> Board {
+    let mut next =
+        super::next_board_by_rows(board, pool, |rows, out| next_rows(board, rule, rows, out));
+    fix_edges(board, rule, &mut next);
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts neighbours of every cell one by one
+    fn naive_next_board(board: &Board, rule: &LifeLike) -> Board {
+        board
+            .map(|&cell, pos| rule.next_state(cell, super::super::get_neighbours_count(board, pos)))
+    }
+
+    fn random_board(width: usize, height: usize, topology: Topology, seed: u64) -> Board {
+        let mut board = Board::create(width, height).with_topology(topology);
+        board.randomize_seeded(seed, 0.4);
+        board
+    }
+
+    #[test]
+    fn matches_naive_counts() {
+        let rules = ["B3/S23", "B36/S23", "B2/S", "B12345678/S012345678", "B/S8"];
+        let sizes = [(1, 1), (1, 5), (5, 1), (63, 4), (64, 3), (65, 5), (130, 7)];
+        for rule in &rules {
+            let rule: LifeLike = rule.parse().unwrap();
+            for (seed, &(width, height)) in sizes.iter().enumerate() {
+                let board = random_board(width, height, Topology::Plane, seed as u64);
+                assert_eq!(
+                    next_board(&board, &rule),
+                    naive_next_board(&board, &rule),
+                    "{} {}x{}",
+                    rule,
+                    width,
+                    height
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fixes_edges_of_every_topology() {
+        let rule = LifeLike::conway();
+        for topology in Topology::all() {
+            for &(width, height) in &[(3, 3), (64, 5), (67, 6), (129, 4)] {
+                let board = random_board(width, height, topology, width as u64);
+                assert_eq!(
+                    next_board(&board, &rule),
+                    naive_next_board(&board, &rule),
+                    "{} {}x{}",
+                    topology,
+                    width,
+                    height
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn births_on_empty_neighbourhoods() {
+        let rule: LifeLike = "B0/S".parse().unwrap();
+        for topology in Topology::all() {
+            let mut board = Board::create(70, 3).with_topology(topology);
+            let next = next_board(&board, &rule);
+            assert_eq!(next.population(), 70 * 3, "{}", topology);
+            // Padding bits past the width stay clear
+            assert_eq!(next, naive_next_board(&board, &rule));
+
+            board.set(0, 1, true);
+            assert_eq!(next_board(&board, &rule), naive_next_board(&board, &rule));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use super::{LifeLike, ParseRuleError, Rule};
+use crate::game_of_life::{Board, ThreadPool};
+
+// Life-like rule in which cells don't die at once, but go through dying
+// states, during which they don't count as neighbours and can't be born,
+// e.g. B2/S/C3 (Brian's Brain) or B2/S345/C4 (Star Wars)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generations {
+    life: LifeLike,
+    // Dead and alive states included
+    states: u8,
+}
+
+impl Generations {
+    pub fn new(life: LifeLike, states: u8) -> Option<Generations> {
+        if states < 2 {
+            return None;
+        }
+        Some(Generations { life, states })
+    }
+
+    pub fn brians_brain() -> Generations {
+        let life = LifeLike::new(&[2], &[]).expect("Internal logic error");
+        Generations::new(life, 3).expect("Internal logic error")
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn next_state(&self, state: u8, neighbours_count: u8) -> u8 {
+        let alive = self.life.next_state(state == 1, neighbours_count);
+        next_state(self.states, state, alive)
+    }
+}
+
+// Next state of a cell of a rule with `states` states, `alive` tells whether
+// a dead or alive cell would be alive under the two-state part of the rule
+fn next_state(states: u8, state: u8, alive: bool) -> u8 {
+    match state {
+        0 => alive.into(),
+        1 if alive => 1,
+        _ if state + 1 < states => state + 1,
+        _ => 0,
+    }
+}
+
+// Next board of a rule with `states` states given the board and the next
+// board of the two-state part of the rule, in which dying cells count as dead
+pub(super) fn decay(board: &Board, mut next: Board, states: u8) -> Board {
+    if states <= 2 {
+        return next;
+    }
+    next.enable_states();
+    for (_, (x, y)) in board.indexed_iter() {
+        let state = board.get_state(x, y).expect("Internal logic error");
+        let alive = *next.get(x, y).expect("Internal logic error");
+        next.set_state(x, y, next_state(states, state, alive));
+    }
+    next
+}
+
+impl std::str::FromStr for Generations {
+    type Err = ParseRuleError;
+
+    // Accepts S/B/C notation, e.g. "345/2/4", and B/S/C notation,
+    // e.g. "B2/S345/C4" or "B2/S345/G4"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRuleError::Empty);
+        }
+        let (life, states) = s.rsplit_once('/').ok_or(ParseRuleError::MissingSeparator)?;
+        let digits = states.trim_start_matches(['C', 'c', 'G', 'g']);
+        let states = digits
+            .parse()
+            .ok()
+            .filter(|&states| states >= 2)
+            .ok_or_else(|| ParseRuleError::InvalidStateCount(states.into()))?;
+        Ok(Generations {
+            life: life.parse()?,
+            states,
+        })
+    }
+}
+
+impl std::fmt::Display for Generations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/C{}", self.life, self.states)
+    }
+}
+
+// Sparse boards and hashlife hold only live cells, so they can't run
+// these rules with more than two states
+impl Rule for Generations {
+    fn next_board(&mut self, board: &Board) -> Board {
+        let next = super::bitwise::next_board(board, &self.life);
+        decay(board, next, self.states)
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        let next = super::bitwise::next_board_parallel(board, &self.life, pool);
+        decay(board, next, self.states)
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn states(&self) -> u8 {
+        self.states
+    }
+}
@@ -0,0 +1,389 @@
+use super::{LifeLike, ParseRuleError, Rule};
+use crate::game_of_life::board::WORD_BITS;
+use crate::game_of_life::{Board, ThreadPool};
+
+// Neighbours in the order of bits of a neighbourhood, clockwise from north
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+// Letters of Hensel notation in canonical order
+const LETTERS: [char; 13] = [
+    'c', 'e', 'k', 'a', 'i', 'n', 'y', 'q', 'j', 'r', 't', 'w', 'z',
+];
+
+// Neighbourhood representing each letter for 1 to 4 neighbours, a letter
+// for 5 to 7 neighbours stands for the complements of its neighbourhoods
+// with 3 to 1 neighbours
+#[rustfmt::skip]
+const CONFIGURATIONS: [(char, u8); 31] = [
+    ('c', 0b0000_0010), ('e', 0b0000_0001),
+    ('c', 0b0000_1010), ('e', 0b0000_0101), ('k', 0b0000_1001),
+    ('a', 0b0000_0011), ('i', 0b0001_0001), ('n', 0b0010_0010),
+    ('c', 0b0010_1010), ('e', 0b0001_0101), ('k', 0b0010_0101),
+    ('a', 0b0000_0111), ('i', 0b1000_0011), ('n', 0b0000_1011),
+    ('y', 0b0010_1001), ('q', 0b0010_0011), ('j', 0b0100_0011),
+    ('r', 0b0001_0011),
+    ('c', 0b1010_1010), ('e', 0b0101_0101), ('k', 0b0100_1011),
+    ('a', 0b0000_1111), ('i', 0b0001_1011), ('n', 0b1000_1011),
+    ('y', 0b0010_1011), ('q', 0b0010_0111), ('j', 0b0101_0011),
+    ('r', 0b0001_0111), ('t', 0b1001_0011), ('w', 0b0110_0011),
+    ('z', 0b0011_0011),
+];
+
+// Smallest of the neighbourhoods the rotations and reflections of
+// `neighbours` give
+fn canonical(neighbours: u8) -> u8 {
+    let reflected = (0..8)
+        .filter(|bit| neighbours & (1 << bit) != 0)
+        .fold(0u8, |reflected, bit| reflected | (1 << ((8 - bit) % 8)));
+    (0..4)
+        .flat_map(|turn| {
+            let turn = 2 * turn;
+            vec![neighbours.rotate_left(turn), reflected.rotate_left(turn)]
+        })
+        .min()
+        .expect("Internal logic error")
+}
+
+// Letter of the neighbourhood in Hensel notation, bits are set for live
+// neighbours as listed in `NEIGHBOURS`, None for 0 and 8 neighbours
+pub fn letter(neighbours: u8) -> Option<char> {
+    let neighbours = if neighbours.count_ones() > 4 {
+        !neighbours
+    } else {
+        neighbours
+    };
+    let neighbours = canonical(neighbours);
+    CONFIGURATIONS
+        .iter()
+        .find(|&&(_, configuration)| canonical(configuration) == neighbours)
+        .map(|&(letter, _)| letter)
+}
+
+// Letters valid for the neighbour count in canonical order
+pub fn letters(count: u8) -> Vec<char> {
+    let count = u32::from(count.min(8 - count.min(8)));
+    LETTERS
+        .iter()
+        .copied()
+        .filter(|&letter| {
+            CONFIGURATIONS
+                .iter()
+                .any(|&(configuration_letter, configuration)| {
+                    configuration_letter == letter && configuration.count_ones() == count
+                })
+        })
+        .collect()
+}
+
+// Isotropic non-totalistic rule on Moore neighbourhood, which depends on
+// the arrangement of the live neighbours up to rotations and reflections,
+// e.g. B2-a/S12 or B3/S23-k in Hensel notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isotropic {
+    // Indexed by neighbourhood, see `letter`
+    birth: [bool; 256],
+    survival: [bool; 256],
+}
+
+impl Default for Isotropic {
+    fn default() -> Self {
+        Isotropic {
+            birth: [false; 256],
+            survival: [false; 256],
+        }
+    }
+}
+
+impl Isotropic {
+    pub fn next_state(&self, cell: bool, neighbours: u8) -> bool {
+        let table = if cell { &self.survival } else { &self.birth };
+        table[usize::from(neighbours)]
+    }
+
+    // Neighbourhood of the cell, see `letter`
+    fn neighbours(board: &Board, (x, y): (i32, i32)) -> u8 {
+        (0..)
+            .zip(NEIGHBOURS.iter())
+            .filter(|(_, &(dx, dy))| board.get_wrapped(x + dx, y + dy))
+            .fold(0, |neighbours, (bit, _)| neighbours | (1 << bit))
+    }
+
+    // Writes bits of the next board for `rows` to `out`, the rows start
+    // at the beginning of `out`
+    fn next_rows(&self, board: &Board, rows: std::ops::Range<usize>, out: &mut [u64]) {
+        let words_per_row = board.words_per_row();
+        let from = rows.start;
+        for y in rows {
+            for x in 0..board.width() {
+                let cell = *board.get(x, y).expect("Internal logic error");
+                let neighbours = Self::neighbours(board, (x as i32, y as i32));
+                if self.next_state(cell, neighbours) {
+                    let index = (y - from) * words_per_row + x / WORD_BITS;
+                    out[index] |= 1 << (x % WORD_BITS);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Birth,
+    Survival,
+}
+
+// Parses neighbour counts with optional letters, e.g. "2-a3ce4"
+fn parse_section(table: &mut [bool; 256], text: &str) -> Result<(), ParseRuleError> {
+    let mut seen = [false; 9];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c.to_digit(10).ok_or(ParseRuleError::InvalidCharacter(c))?;
+        let slot = seen
+            .get_mut(count as usize)
+            .ok_or(ParseRuleError::InvalidNeighbourCount(c))?;
+        if *slot {
+            return Err(ParseRuleError::DuplicateNeighbourCount(c));
+        }
+        *slot = true;
+
+        let valid = letters(count as u8);
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut listed = Vec::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            if !valid.contains(&letter) {
+                return Err(ParseRuleError::InvalidLetter(c, letter));
+            }
+            listed.push(letter);
+        }
+        if negated && listed.is_empty() {
+            return Err(ParseRuleError::InvalidCharacter('-'));
+        }
+        for neighbours in (0..=255u8).filter(|n| n.count_ones() == count) {
+            let included = match letter(neighbours) {
+                Some(letter) if !listed.is_empty() => listed.contains(&letter) != negated,
+                _ => true,
+            };
+            table[usize::from(neighbours)] |= included;
+        }
+    }
+    Ok(())
+}
+
+// Writes counts of the table with the letters of their neighbourhoods,
+// using the shorter of the listed and the negated form
+fn write_section(f: &mut std::fmt::Formatter<'_>, table: &[bool; 256]) -> std::fmt::Result {
+    for count in 0..=8u8 {
+        let all = letters(count);
+        let included: Vec<char> = all
+            .iter()
+            .copied()
+            .filter(|&letter| {
+                (0..=255u8)
+                    .filter(|n| n.count_ones() == u32::from(count))
+                    .any(|n| table[usize::from(n)] && self::letter(n) == Some(letter))
+            })
+            .collect();
+        let any = (0..=255u8)
+            .filter(|n| n.count_ones() == u32::from(count))
+            .any(|n| table[usize::from(n)]);
+        if !any {
+            continue;
+        }
+        write!(f, "{}", count)?;
+        if included.len() == all.len() {
+            continue;
+        }
+        if included.len() * 2 <= all.len() {
+            included
+                .iter()
+                .try_for_each(|letter| write!(f, "{}", letter))?;
+        } else {
+            write!(f, "-")?;
+            all.iter()
+                .filter(|letter| !included.contains(letter))
+                .try_for_each(|letter| write!(f, "{}", letter))?;
+        }
+    }
+    Ok(())
+}
+
+impl std::str::FromStr for Isotropic {
+    type Err = ParseRuleError;
+
+    // Accepts B/S notation, e.g. "B2-a/S12", "S12/B2-a" or "B2-aS12"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRuleError::Empty);
+        }
+        let mut birth = None;
+        let mut survival = None;
+        let mut section = None;
+        for c in s.chars() {
+            // Hensel letters don't include 'b' and 's'
+            match c {
+                'B' | 'b' if birth.is_none() => {
+                    birth = Some(String::new());
+                    section = Some(Section::Birth);
+                }
+                'S' | 's' if survival.is_none() => {
+                    survival = Some(String::new());
+                    section = Some(Section::Survival);
+                }
+                'B' | 'b' | 'S' | 's' => return Err(ParseRuleError::DuplicateSection(c)),
+                '/' if section.is_some() => section = None,
+                _ => {
+                    let text = match section.ok_or(ParseRuleError::MissingSection)? {
+                        Section::Birth => &mut birth,
+                        Section::Survival => &mut survival,
+                    };
+                    text.as_mut().expect("Internal logic error").push(c);
+                }
+            }
+        }
+        if s.ends_with('/') {
+            return Err(ParseRuleError::InvalidCharacter('/'));
+        }
+        let (birth, survival) = match (birth, survival) {
+            (Some(birth), Some(survival)) => (birth, survival),
+            _ => return Err(ParseRuleError::MissingSeparator),
+        };
+        let mut rule = Isotropic::default();
+        parse_section(&mut rule.birth, &birth)?;
+        parse_section(&mut rule.survival, &survival)?;
+        Ok(rule)
+    }
+}
+
+impl std::fmt::Display for Isotropic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "B")?;
+        write_section(f, &self.birth)?;
+        write!(f, "/S")?;
+        write_section(f, &self.survival)
+    }
+}
+
+impl Rule for Isotropic {
+    fn next_board(&mut self, board: &Board) -> Board {
+        let mut next = Board::create(board.width(), board.height()).with_topology(board.topology);
+        self.next_rows(board, 0..board.height(), &mut next.words);
+        next
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        super::next_board_by_rows(board, pool, |rows, out| self.next_rows(board, rows, out))
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    // Rules depending only on the number of neighbours are life-like
+    fn life_like(&self) -> Option<LifeLike> {
+        let counts = |table: &[bool; 256]| -> Option<Vec<u8>> {
+            let mut counts = Vec::new();
+            for count in 0..=8u8 {
+                let mut cells = (0..=255u8)
+                    .filter(|n| n.count_ones() == u32::from(count))
+                    .map(|n| table[usize::from(n)]);
+                let first = cells.next().expect("Internal logic error");
+                if !cells.all(|cell| cell == first) {
+                    return None;
+                }
+                if first {
+                    counts.push(count);
+                }
+            }
+            Some(counts)
+        };
+        LifeLike::new(&counts(&self.birth)?, &counts(&self.survival)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn letters_cover_all_neighbourhoods() {
+        let classes: HashSet<_> = (0..=255u8).map(|n| (n.count_ones(), letter(n))).collect();
+        assert_eq!(classes.len(), 51);
+        for count in 1..=7u8 {
+            let found: HashSet<_> = classes
+                .iter()
+                .filter(|&&(n, _)| n == u32::from(count))
+                .map(|&(_, letter)| letter.unwrap())
+                .collect();
+            assert_eq!(found, letters(count).into_iter().collect(), "{}", count);
+        }
+    }
+
+    #[test]
+    fn single_letters_select_their_neighbourhoods() {
+        for count in 1..=7u8 {
+            for letter in letters(count) {
+                let rule: Isotropic = format!("B{}{}/S", count, letter).parse().unwrap();
+                for n in 0..=255u8 {
+                    let selected =
+                        n.count_ones() == u32::from(count) && self::letter(n) == Some(letter);
+                    assert_eq!(
+                        rule.next_state(false, n),
+                        selected,
+                        "{}{} {:08b}",
+                        count,
+                        letter,
+                        n
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn writes_canonical_notation() {
+        let cases = [
+            ("B2c3aeijr/S23", "B2c3eaijr/S23"),
+            ("B3aeijrc/S", "B3-knyq/S"),
+            ("B2-a/S12", "B2-a/S12"),
+            ("S12/B2ceikn", "B2-a/S12"),
+            ("B3-cekainyqj/S2-c", "B3r/S2-c"),
+            ("B2cekainS2", "B2/S2"),
+            ("B3cekainyqjr/S2cekain3", "B3/S23"),
+        ];
+        for &(rule, canonical) in &cases {
+            let parsed: Isotropic = rule.parse().unwrap_or_else(|e| panic!("{}: {}", rule, e));
+            assert_eq!(parsed.to_string(), canonical);
+        }
+    }
+
+    #[test]
+    fn blinker_needs_straight_births() {
+        let blinker = |rule: &str| {
+            let mut rule: Isotropic = rule.parse().unwrap();
+            let mut board = Board::create(5, 5);
+            for y in 1..4 {
+                board.set(2, y, true);
+            }
+            (0..4)
+                .map(|_| {
+                    board = rule.next_board(&board);
+                    board.population()
+                })
+                .collect::<Vec<_>>()
+        };
+        // Cells next to the blinker see three neighbours in a row
+        assert_eq!(blinker("B3i/S2"), [3, 3, 3, 3]);
+        assert_eq!(blinker("B3-i/S23"), [1, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,433 @@
+use super::{Neighbourhood, ParseRuleError, Rule};
+use crate::game_of_life::board::WORD_BITS;
+use crate::game_of_life::{Board, SparseBoard, ThreadPool};
+
+pub const MAX_RANGE: u32 = 500;
+
+// Rectangle [from, to) of the neighbourhood relative to the centre cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Band {
+    from: (i64, i64),
+    to: (i64, i64),
+}
+
+// Outer totalistic rule counting live cells in a neighbourhood of the given
+// range, in the notation of Golly, e.g. R5,C0,M1,S34..58,B34..45,NM (Bosco's
+// rule); dying states work as in `Generations`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargerThanLife {
+    range: u32,
+    // Dead and alive states included
+    states: u8,
+    // Whether the cell counts itself
+    middle: bool,
+    // Inclusive ranges of neighbour counts
+    survival: (u32, u32),
+    birth: (u32, u32),
+    neighbourhood: Neighbourhood,
+    // Neighbourhood split into rectangles with rows of the same width
+    bands: Vec<Band>,
+}
+
+impl LargerThanLife {
+    pub fn new(
+        range: u32,
+        states: u8,
+        middle: bool,
+        survival: (u32, u32),
+        birth: (u32, u32),
+        neighbourhood: Neighbourhood,
+    ) -> Option<LargerThanLife> {
+        if range == 0 || range > MAX_RANGE {
+            return None;
+        }
+        let mut bands: Vec<Band> = Vec::new();
+        for (dy, (from_x, to_x)) in (-i64::from(range)..).zip(neighbourhood.row_spans(range)) {
+            match bands.last_mut() {
+                Some(band) if (band.from.0, band.to.0) == (from_x, to_x) => band.to.1 = dy + 1,
+                _ => bands.push(Band {
+                    from: (from_x, dy),
+                    to: (to_x, dy + 1),
+                }),
+            }
+        }
+        Some(LargerThanLife {
+            range,
+            states: states.max(2),
+            middle,
+            survival,
+            birth,
+            neighbourhood,
+            bands,
+        })
+    }
+
+    // Bosco's rule, R5,C0,M1,S34..58,B34..45,NM
+    pub fn bosco() -> LargerThanLife {
+        LargerThanLife::new(5, 2, true, (34, 58), (34, 45), Neighbourhood::Moore)
+            .expect("Internal logic error")
+    }
+
+    pub fn range(&self) -> u32 {
+        self.range
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    // Cells of the neighbourhood, including the centre one
+    pub fn neighbourhood_size(&self) -> u32 {
+        self.neighbourhood.size(self.range)
+    }
+
+    pub fn next_state(&self, cell: bool, neighbours_count: u32) -> bool {
+        let (min, max) = if cell { self.survival } else { self.birth };
+        (min..=max).contains(&neighbours_count)
+    }
+
+    // Writes bits of the next board for `rows` to `out`, the rows start
+    // at the beginning of `out`
+    fn next_rows(
+        &self,
+        board: &Board,
+        counts: &Counts,
+        rows: std::ops::Range<usize>,
+        out: &mut [u64],
+    ) {
+        let words_per_row = board.words_per_row();
+        let range = i64::from(self.range);
+        let from = rows.start;
+        for y in rows {
+            for x in 0..board.width() {
+                let cell = *board.get(x, y).expect("Internal logic error");
+                let (padded_x, padded_y) = (x as i64 + range, y as i64 + range);
+                let mut count: u32 = self
+                    .bands
+                    .iter()
+                    .map(|band| {
+                        counts.sum(
+                            (padded_x + band.from.0, padded_y + band.from.1),
+                            (padded_x + band.to.0, padded_y + band.to.1),
+                        )
+                    })
+                    .sum();
+                if cell && !self.middle {
+                    count -= 1;
+                }
+                if self.next_state(cell, count) {
+                    let index = (y - from) * words_per_row + x / WORD_BITS;
+                    out[index] |= 1 << (x % WORD_BITS);
+                }
+            }
+        }
+    }
+}
+
+// Summed-area table of live cells of the board extended by `range` cells
+// on every side according to its topology
+struct Counts {
+    // Live cells in the rectangle [0, x) x [0, y) at index y * width + x
+    table: Vec<u32>,
+    width: usize,
+}
+
+impl Counts {
+    fn create(board: &Board, range: u32) -> Counts {
+        let range = range as i32;
+        let padded_width = board.width() + 2 * range as usize;
+        let padded_height = board.height() + 2 * range as usize;
+        let width = padded_width + 1;
+        let mut table = vec![0; width * (padded_height + 1)];
+        for y in 0..padded_height {
+            let mut row_sum = 0;
+            for x in 0..padded_width {
+                row_sum += u32::from(board.get_wrapped(x as i32 - range, y as i32 - range));
+                table[(y + 1) * width + x + 1] = table[y * width + x + 1] + row_sum;
+            }
+        }
+        Counts { table, width }
+    }
+
+    // Live cells in the rectangle [from, to) of the extended board
+    fn sum(&self, from: (i64, i64), to: (i64, i64)) -> u32 {
+        let at = |x: i64, y: i64| self.table[y as usize * self.width + x as usize];
+        at(to.0, to.1) + at(from.0, from.1) - at(from.0, to.1) - at(to.0, from.1)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, parameter: &str) -> Result<T, ParseRuleError> {
+    text.parse()
+        .map_err(|_| ParseRuleError::InvalidParameter(parameter.into()))
+}
+
+fn parse_counts(text: &str, parameter: &str) -> Result<(u32, u32), ParseRuleError> {
+    // A single count is accepted in place of a range
+    let (min, max) = text.split_once("..").unwrap_or((text, text));
+    Ok((parse_number(min, parameter)?, parse_number(max, parameter)?))
+}
+
+impl std::str::FromStr for LargerThanLife {
+    type Err = ParseRuleError;
+
+    // Parameters are separated by commas, C, M and N default to two states,
+    // not counting the middle cell and Moore neighbourhood
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRuleError::Empty);
+        }
+        let mut range = None;
+        let mut states = 0;
+        let mut middle = false;
+        let mut survival = None;
+        let mut birth = None;
+        let mut neighbourhood = Neighbourhood::default();
+        let mut seen = Vec::new();
+        for parameter in s.split(',') {
+            let parameter = parameter.trim();
+            let mut chars = parameter.chars();
+            let key = chars
+                .next()
+                .ok_or_else(|| ParseRuleError::InvalidParameter(parameter.into()))?
+                .to_ascii_uppercase();
+            if seen.contains(&key) {
+                return Err(ParseRuleError::DuplicateSection(key));
+            }
+            seen.push(key);
+            let value = chars.as_str();
+            match key {
+                'R' => range = Some(parse_number(value, parameter)?),
+                'C' => {
+                    states = parse_number(value, parameter)
+                        .map_err(|_| ParseRuleError::InvalidStateCount(value.into()))?
+                }
+                'M' => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(ParseRuleError::InvalidParameter(parameter.into())),
+                    }
+                }
+                'S' => survival = Some(parse_counts(value, parameter)?),
+                'B' => birth = Some(parse_counts(value, parameter)?),
+                'N' => {
+                    neighbourhood = Neighbourhood::all()
+                        .into_iter()
+                        .find(|shape| value.eq_ignore_ascii_case(&shape.letter().to_string()))
+                        .ok_or_else(|| ParseRuleError::InvalidParameter(parameter.into()))?
+                }
+                _ => return Err(ParseRuleError::InvalidCharacter(key)),
+            }
+        }
+        let range = range.ok_or(ParseRuleError::MissingParameter('R'))?;
+        let survival = survival.ok_or(ParseRuleError::MissingParameter('S'))?;
+        let birth = birth.ok_or(ParseRuleError::MissingParameter('B'))?;
+        LargerThanLife::new(range, states, middle, survival, birth, neighbourhood)
+            .ok_or_else(|| ParseRuleError::InvalidParameter(format!("R{}", range)))
+    }
+}
+
+impl std::fmt::Display for LargerThanLife {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Golly writes two states as C0
+        let states = if self.states == 2 { 0 } else { self.states };
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.range,
+            states,
+            u8::from(self.middle),
+            self.survival.0,
+            self.survival.1,
+            self.birth.0,
+            self.birth.1,
+            self.neighbourhood.letter()
+        )
+    }
+}
+
+impl Rule for LargerThanLife {
+    fn next_board(&mut self, board: &Board) -> Board {
+        let counts = Counts::create(board, self.range);
+        let mut next = Board::create(board.width(), board.height()).with_topology(board.topology);
+        self.next_rows(board, &counts, 0..board.height(), &mut next.words);
+        super::generations::decay(board, next, self.states)
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        let counts = Counts::create(board, self.range);
+        let next = super::next_board_by_rows(board, pool, |rows, out| {
+            self.next_rows(board, &counts, rows, out)
+        });
+        super::generations::decay(board, next, self.states)
+    }
+
+    // Steps the bounding box of the pattern extended by the range, fails with
+    // dying cells, which sparse boards can't hold
+    fn next_sparse_board(&mut self, board: &SparseBoard) -> Option<SparseBoard> {
+        if self.states > 2 {
+            return None;
+        }
+        let mut next = SparseBoard::create();
+        let (from, to) = match board.bounding_box() {
+            Some(bounding_box) => bounding_box,
+            None => return Some(next),
+        };
+        let range = i64::from(self.range);
+        let pos = (from.0 - range, from.1 - range);
+        let mut view = Board::create(
+            (to.0 - from.0 + 2 * range) as usize,
+            (to.1 - from.1 + 2 * range) as usize,
+        );
+        board.write_board(&mut view, pos);
+        next.read_board(&self.next_board(&view), pos);
+        Some(next)
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn states(&self) -> u8 {
+        self.states
+    }
+
+    fn hexagonal(&self) -> bool {
+        self.neighbourhood == Neighbourhood::Hexagonal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::Topology;
+
+    // Counts neighbours of every cell one by one
+    fn naive_next_board(rule: &LargerThanLife, board: &Board) -> Board {
+        let offsets = rule.neighbourhood.offsets(rule.range);
+        board.map(|&cell, (x, y)| {
+            let mut count = offsets
+                .iter()
+                .filter(|&&(dx, dy)| board.get_wrapped(x + dx, y + dy))
+                .count() as u32;
+            if cell && rule.middle {
+                count += 1;
+            }
+            rule.next_state(cell, count)
+        })
+    }
+
+    fn random_board(width: usize, height: usize, topology: Topology, seed: u64) -> Board {
+        let mut board = Board::create(width, height).with_topology(topology);
+        board.randomize_seeded(seed, 0.45);
+        board
+    }
+
+    #[test]
+    fn parses_rule_strings() {
+        let rule: LargerThanLife = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+        assert_eq!(rule, LargerThanLife::bosco());
+        assert_eq!(rule.neighbourhood_size(), 121);
+        let rule: LargerThanLife = " r2, b3, s2..3, nh ".parse().unwrap();
+        assert_eq!(rule.to_string(), "R2,C0,M0,S2..3,B3..3,NH");
+        assert_eq!(rule.neighbourhood(), Neighbourhood::Hexagonal);
+        let rule: LargerThanLife = "R3,C4,M0,S1..2,B3..3,NC".parse().unwrap();
+        assert_eq!(rule.states(), 4);
+    }
+
+    #[test]
+    fn writes_own_notation() {
+        for notation in &[
+            "R5,C0,M1,S34..58,B34..45,NM",
+            "R1,C0,M0,S2..3,B3..3,NM",
+            "R7,C10,M0,S10..20,B12..14,NN",
+            "R2,C3,M1,S0..0,B1..2,NC",
+            "R3,C0,M0,S4..9,B5..6,NH",
+        ] {
+            let rule: LargerThanLife = notation.parse().unwrap();
+            assert_eq!(&rule.to_string(), notation);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let error = |rule: &str| rule.parse::<LargerThanLife>().unwrap_err();
+        let max = format!("R{},C0,M0,S1..2,B3..3,NM", MAX_RANGE);
+        assert!(max.parse::<LargerThanLife>().is_ok());
+        let too_large = format!("R{},C0,M0,S1..2,B3..3,NM", MAX_RANGE + 1);
+        assert_eq!(
+            error(&too_large),
+            ParseRuleError::InvalidParameter(format!("R{}", MAX_RANGE + 1))
+        );
+        assert_eq!(
+            error("R0,S1,B1"),
+            ParseRuleError::InvalidParameter("R0".into())
+        );
+        assert_eq!(error("S1,B1"), ParseRuleError::MissingParameter('R'));
+        assert_eq!(error("R1,S1,B1,R2"), ParseRuleError::DuplicateSection('R'));
+        assert_eq!(
+            error("R1,S1,B1,NX"),
+            ParseRuleError::InvalidParameter("NX".into())
+        );
+        assert_eq!(
+            error("R1,S1..x,B1"),
+            ParseRuleError::InvalidParameter("S1..x".into())
+        );
+        assert_eq!(error(""), ParseRuleError::Empty);
+    }
+
+    #[test]
+    fn bosco_matches_naive_counts() {
+        let mut rule = LargerThanLife::bosco();
+        let mut board = random_board(40, 30, Topology::Plane, 1);
+        for generation in 0..5 {
+            let next = rule.next_board(&board);
+            assert_eq!(
+                next,
+                naive_next_board(&rule, &board),
+                "generation {}",
+                generation
+            );
+            board = next;
+        }
+    }
+
+    #[test]
+    fn counts_wrap_around_surfaces() {
+        for topology in Topology::all() {
+            for neighbourhood in Neighbourhood::all() {
+                for &middle in &[false, true] {
+                    let mut rule =
+                        LargerThanLife::new(3, 2, middle, (5, 14), (6, 9), neighbourhood).unwrap();
+                    // Range larger than the board wraps around several times
+                    for &(width, height) in &[(20, 13), (70, 5), (2, 3)] {
+                        let board = random_board(width, height, topology, width as u64);
+                        assert_eq!(
+                            rule.next_board(&board),
+                            naive_next_board(&rule, &board),
+                            "{} {}x{} {}",
+                            topology,
+                            width,
+                            height,
+                            rule
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dying_cells_decay() {
+        let mut rule: LargerThanLife = "R1,C3,M0,S8..8,B1..1,NM".parse().unwrap();
+        let mut board = Board::create(5, 5);
+        board.set(2, 2, true);
+        board = rule.next_board(&board);
+        assert_eq!(board.get_state(2, 2), Some(2));
+        assert_eq!(board.population(), 8);
+        board = rule.next_board(&board);
+        assert_eq!(board.get_state(2, 2), Some(0));
+        assert!(rule.next_sparse_board(&SparseBoard::create()).is_none());
+    }
+}
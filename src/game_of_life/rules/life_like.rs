@@ -0,0 +1,211 @@
+use super::Rule;
+use crate::game_of_life::{Board, ThreadPool};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRuleError {
+    Empty,
+    InvalidCharacter(char),
+    InvalidNeighbourCount(char),
+    DuplicateNeighbourCount(char),
+    DuplicateSection(char),
+    MissingSection,
+    MissingSeparator,
+    InvalidStateCount(String),
+    InvalidParameter(String),
+    MissingParameter(char),
+    InvalidLetter(char, char),
+    TooManyNeighbours(u8, u8),
+    InvalidTable(String),
+}
+
+impl std::fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Rule string is empty"),
+            Self::InvalidCharacter(c) => write!(f, "Unexpected character '{}' in rule", c),
+            Self::InvalidNeighbourCount(c) => {
+                write!(f, "Neighbour count '{}' is out of range 0-8", c)
+            }
+            Self::DuplicateNeighbourCount(c) => {
+                write!(f, "Neighbour count '{}' is listed twice", c)
+            }
+            Self::DuplicateSection(c) => write!(f, "Section '{}' is listed twice", c),
+            Self::MissingSection => write!(f, "Neighbour counts have to follow 'B' or 'S'"),
+            Self::MissingSeparator => {
+                write!(f, "Rule has to be in 'B../S..' or '../..' (S/B) notation")
+            }
+            Self::InvalidStateCount(states) => {
+                write!(f, "Number of states '{}' is out of range 2-255", states)
+            }
+            Self::InvalidParameter(parameter) => write!(f, "Invalid parameter '{}'", parameter),
+            Self::MissingParameter(c) => write!(f, "Parameter '{}' is missing", c),
+            Self::InvalidLetter(count, letter) => write!(
+                f,
+                "Letter '{}' does not describe any neighbourhood with {} neighbours",
+                letter, count
+            ),
+            Self::TooManyNeighbours(count, neighbours) => write!(
+                f,
+                "Neighbour count {} is higher than the {} neighbours of the grid",
+                count, neighbours
+            ),
+            Self::InvalidTable(message) => write!(f, "Invalid rule table: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+// Outer totalistic rule on Moore neighbourhood, e.g. B3/S23 for Conway's life
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifeLike {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl LifeLike {
+    pub fn new(birth: &[u8], survival: &[u8]) -> Option<LifeLike> {
+        let mut rule = LifeLike::default();
+        for &count in birth {
+            *rule.birth.get_mut(usize::from(count))? = true;
+        }
+        for &count in survival {
+            *rule.survival.get_mut(usize::from(count))? = true;
+        }
+        Some(rule)
+    }
+
+    pub fn conway() -> LifeLike {
+        LifeLike::new(&[3], &[2, 3]).expect("Internal logic error")
+    }
+
+    pub fn next_state(&self, cell: bool, neighbours_count: u8) -> bool {
+        let counts = if cell { &self.survival } else { &self.birth };
+        counts
+            .get(usize::from(neighbours_count))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn birth(&self) -> impl '_ + Iterator<Item = u8> {
+        (0..).zip(self.birth.iter()).filter(|p| *p.1).map(|p| p.0)
+    }
+
+    pub fn survival(&self) -> impl '_ + Iterator<Item = u8> {
+        (0..)
+            .zip(self.survival.iter())
+            .filter(|p| *p.1)
+            .map(|p| p.0)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Birth,
+    Survival,
+}
+
+fn parse_counts(counts: &mut [bool; 9], digits: &str) -> Result<(), ParseRuleError> {
+    for c in digits.chars() {
+        let count = c.to_digit(10).ok_or(ParseRuleError::InvalidCharacter(c))?;
+        let slot = counts
+            .get_mut(count as usize)
+            .ok_or(ParseRuleError::InvalidNeighbourCount(c))?;
+        if *slot {
+            return Err(ParseRuleError::DuplicateNeighbourCount(c));
+        }
+        *slot = true;
+    }
+    Ok(())
+}
+
+impl std::str::FromStr for LifeLike {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRuleError::Empty);
+        }
+        let mut rule = LifeLike::default();
+
+        if !s.contains(|c: char| c.is_ascii_alphabetic()) {
+            // Legacy S/B notation, e.g. "23/3"
+            let mut parts = s.split('/');
+            let survival = parts.next().ok_or(ParseRuleError::MissingSeparator)?;
+            let birth = parts.next().ok_or(ParseRuleError::MissingSeparator)?;
+            if parts.next().is_some() {
+                return Err(ParseRuleError::InvalidCharacter('/'));
+            }
+            parse_counts(&mut rule.survival, survival)?;
+            parse_counts(&mut rule.birth, birth)?;
+            return Ok(rule);
+        }
+
+        // B/S notation, e.g. "B3/S23", "S23/B3" or "B3S23"
+        let mut seen_birth = false;
+        let mut seen_survival = false;
+        let mut section = None;
+        for c in s.chars() {
+            match c {
+                'B' | 'b' if !seen_birth => {
+                    seen_birth = true;
+                    section = Some(Section::Birth);
+                }
+                'S' | 's' if !seen_survival => {
+                    seen_survival = true;
+                    section = Some(Section::Survival);
+                }
+                'B' | 'b' | 'S' | 's' => return Err(ParseRuleError::DuplicateSection(c)),
+                '/' if section.is_some() => section = None,
+                '0'..='9' => {
+                    let counts = match section.ok_or(ParseRuleError::MissingSection)? {
+                        Section::Birth => &mut rule.birth,
+                        Section::Survival => &mut rule.survival,
+                    };
+                    parse_counts(counts, c.encode_utf8(&mut [0; 4]))?;
+                }
+                _ => return Err(ParseRuleError::InvalidCharacter(c)),
+            }
+        }
+        if s.ends_with('/') {
+            return Err(ParseRuleError::InvalidCharacter('/'));
+        }
+        if !(seen_birth && seen_survival) {
+            return Err(ParseRuleError::MissingSeparator);
+        }
+        Ok(rule)
+    }
+}
+
+impl std::fmt::Display for LifeLike {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "B")?;
+        for count in self.birth() {
+            write!(f, "{}", count)?;
+        }
+        write!(f, "/S")?;
+        for count in self.survival() {
+            write!(f, "{}", count)?;
+        }
+        Ok(())
+    }
+}
+
+impl Rule for LifeLike {
+    fn next_board(&mut self, board: &Board) -> Board {
+        super::bitwise::next_board(board, self)
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        super::bitwise::next_board_parallel(board, self, pool)
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn life_like(&self) -> Option<LifeLike> {
+        Some(self.clone())
+    }
+}
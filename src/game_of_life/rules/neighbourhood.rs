@@ -0,0 +1,75 @@
+// Cells counted as neighbours of the cell in the centre
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Neighbourhood {
+    // Square of cells at most `range` cells away in both directions
+    #[default]
+    Moore,
+    // Diamond of cells at most `range` steps away
+    VonNeumann,
+    // Cells at Euclidean distance less than `range` + 1/2
+    Circular,
+    // Cells at most `range` steps away on a hexagonal grid, which is the
+    // square grid with rows shifted by half a cell each, so that the Moore
+    // neighbourhood without the north-east and south-west cells is left
+    Hexagonal,
+}
+
+impl Neighbourhood {
+    pub fn all() -> Vec<Neighbourhood> {
+        vec![
+            Self::Moore,
+            Self::VonNeumann,
+            Self::Circular,
+            Self::Hexagonal,
+        ]
+    }
+
+    // Letter following 'N' in Larger than Life rule strings
+    pub(super) fn letter(&self) -> char {
+        match self {
+            Self::Moore => 'M',
+            Self::VonNeumann => 'N',
+            Self::Circular => 'C',
+            Self::Hexagonal => 'H',
+        }
+    }
+
+    // Columns [from, to) of each row of the neighbourhood relative to the
+    // centre, rows go from -range to range
+    pub fn row_spans(&self, range: u32) -> impl Iterator<Item = (i64, i64)> {
+        let shape = *self;
+        let range = i64::from(range);
+        (-range..=range).map(move |dy| {
+            let (from, to) = match shape {
+                Self::Moore => (-range, range),
+                Self::VonNeumann => (dy.abs() - range, range - dy.abs()),
+                Self::Circular => {
+                    let limit = range * range + range - dy * dy;
+                    let half_width = (0..=range)
+                        .take_while(|dx| dx * dx <= limit)
+                        .last()
+                        .unwrap_or(0);
+                    (-half_width, half_width)
+                }
+                Self::Hexagonal => ((dy - range).max(-range), (dy + range).min(range)),
+            };
+            (from, to + 1)
+        })
+    }
+
+    // Positions of the neighbours relative to the centre
+    pub fn offsets(&self, range: u32) -> Vec<(i32, i32)> {
+        (-(range as i32)..)
+            .zip(self.row_spans(range))
+            .flat_map(|(dy, (from, to))| (from as i32..to as i32).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .collect()
+    }
+
+    // Cells of the neighbourhood, including the centre one
+    pub fn size(&self, range: u32) -> u32 {
+        self.row_spans(range)
+            .map(|(from, to)| (to - from) as u32)
+            .sum()
+    }
+}
@@ -0,0 +1,118 @@
+use super::{LifeLike, Neighbourhood, ParseRuleError, Rule};
+use crate::game_of_life::board::WORD_BITS;
+use crate::game_of_life::{Board, ThreadPool};
+
+// Outer totalistic rule on the direct neighbours of a von Neumann or
+// hexagonal neighbourhood, written as B/S notation with a suffix like in
+// Golly, e.g. B2/S34H or B1/S1V
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OuterTotalistic {
+    life: LifeLike,
+    neighbourhood: Neighbourhood,
+    offsets: Vec<(i32, i32)>,
+}
+
+impl OuterTotalistic {
+    // Fails for neighbourhoods without a suffix and counts higher than the
+    // number of neighbours
+    pub fn new(
+        life: LifeLike,
+        neighbourhood: Neighbourhood,
+    ) -> Result<OuterTotalistic, ParseRuleError> {
+        if suffix(neighbourhood).is_none() {
+            let parameter = format!("N{}", neighbourhood.letter());
+            return Err(ParseRuleError::InvalidParameter(parameter));
+        }
+        let offsets = neighbourhood.offsets(1);
+        let neighbours = offsets.len() as u8;
+        if let Some(count) = life
+            .birth()
+            .chain(life.survival())
+            .find(|&c| c > neighbours)
+        {
+            return Err(ParseRuleError::TooManyNeighbours(count, neighbours));
+        }
+        Ok(OuterTotalistic {
+            life,
+            neighbourhood,
+            offsets,
+        })
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    // Writes bits of the next board for `rows` to `out`, the rows start
+    // at the beginning of `out`
+    fn next_rows(&self, board: &Board, rows: std::ops::Range<usize>, out: &mut [u64]) {
+        let words_per_row = board.words_per_row();
+        let from = rows.start;
+        for y in rows {
+            for x in 0..board.width() {
+                let cell = *board.get(x, y).expect("Internal logic error");
+                let (x_pos, y_pos) = (x as i32, y as i32);
+                let count = self
+                    .offsets
+                    .iter()
+                    .filter(|&&(dx, dy)| board.get_wrapped(x_pos + dx, y_pos + dy))
+                    .count();
+                if self.life.next_state(cell, count as u8) {
+                    let index = (y - from) * words_per_row + x / WORD_BITS;
+                    out[index] |= 1 << (x % WORD_BITS);
+                }
+            }
+        }
+    }
+}
+
+fn suffix(neighbourhood: Neighbourhood) -> Option<char> {
+    match neighbourhood {
+        Neighbourhood::VonNeumann => Some('V'),
+        Neighbourhood::Hexagonal => Some('H'),
+        Neighbourhood::Moore | Neighbourhood::Circular => None,
+    }
+}
+
+impl std::str::FromStr for OuterTotalistic {
+    type Err = ParseRuleError;
+
+    // Accepts the notations of `LifeLike` followed by 'H' or 'V'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let last = s.chars().last().ok_or(ParseRuleError::Empty)?;
+        let neighbourhood = Neighbourhood::all()
+            .into_iter()
+            .find(|&neighbourhood| suffix(neighbourhood) == Some(last.to_ascii_uppercase()))
+            .ok_or(ParseRuleError::InvalidCharacter(last))?;
+        let life: LifeLike = s[..s.len() - last.len_utf8()].parse()?;
+        OuterTotalistic::new(life, neighbourhood)
+    }
+}
+
+impl std::fmt::Display for OuterTotalistic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = suffix(self.neighbourhood).expect("Internal logic error");
+        write!(f, "{}{}", self.life, suffix)
+    }
+}
+
+impl Rule for OuterTotalistic {
+    fn next_board(&mut self, board: &Board) -> Board {
+        let mut next = Board::create(board.width(), board.height()).with_topology(board.topology);
+        self.next_rows(board, 0..board.height(), &mut next.words);
+        next
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        super::next_board_by_rows(board, pool, |rows, out| self.next_rows(board, rows, out))
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn hexagonal(&self) -> bool {
+        self.neighbourhood == Neighbourhood::Hexagonal
+    }
+}
@@ -0,0 +1,897 @@
+// Rule tables in the format of Golly's .rule files, e.g.
+//
+//   @RULE WireWorld
+//   @TABLE
+//   n_states:4
+//   neighborhood:Moore
+//   symmetries:rotate8
+//   var a={0,1,2,3}
+//   ...
+//   1,a,b,c,d,e,f,g,h,2
+//   @COLORS
+//   1 0 128 255
+//
+// Transitions list the states of the cell, its neighbours and the new state
+// of the cell, the first matching transition is used and cells without
+// one keep their state. Variables occurring more than once in a transition
+// take the same state in all places. Symmetric transitions aren't listed,
+// the neighbours are rearranged instead when looking up the new state.
+//
+// Tables are written by their @RULE name and read from "<name>.rule" in
+// the rules directories, see `RuleTable::directories`.
+
+use super::{ParseRuleError, Rule};
+use crate::game_of_life::formats::ParseError;
+use crate::game_of_life::{Board, ThreadPool};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Neighbours of the neighbourhoods in the order used in transitions, all
+// except the one-dimensional go clockwise from north
+const MOORE: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+const VON_NEUMANN: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+// See `Neighbourhood::Hexagonal`
+const HEXAGONAL: [(i32, i32); 6] = [(0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)];
+const ONE_DIMENSIONAL: [(i32, i32); 2] = [(-1, 0), (1, 0)];
+const MAX_NEIGHBOURS: usize = MOORE.len();
+
+// Set of states, bit `state` is set for each state of the set
+type States = [u64; 4];
+
+fn single(state: u8) -> States {
+    let mut states = [0; 4];
+    states[usize::from(state / 64)] |= 1 << (state % 64);
+    states
+}
+
+fn contains(states: &States, state: u8) -> bool {
+    states[usize::from(state / 64)] & (1 << (state % 64)) != 0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Symmetry {
+    // Permutations of the neighbours, including the identity
+    Permutations(Vec<Vec<usize>>),
+    // All permutations of the neighbours
+    Permute,
+}
+
+// Permutations of `n` neighbours generated by rotations by `step` and,
+// if `reflect` is set, the reflection `mirror`
+fn permutations(n: usize, step: usize, mirror: Option<&dyn Fn(usize) -> usize>) -> Symmetry {
+    let mut permutations = Vec::new();
+    for turn in (0..n).step_by(step) {
+        let rotation: Vec<usize> = (0..n).map(|i| (i + turn) % n).collect();
+        if let Some(mirror) = mirror {
+            permutations.push(rotation.iter().map(|&i| mirror(i)).collect());
+        }
+        permutations.push(rotation);
+    }
+    Symmetry::Permutations(permutations)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    State(u8),
+    Variable(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    State(u8),
+    // State of the bound variable with this index
+    Variable(usize),
+}
+
+// Transition as listed in the table, without the symmetric ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Transition {
+    // States of the cell (position 0) and its neighbours
+    inputs: Vec<States>,
+    // Positions of each variable occurring more than once, which have to be
+    // in the same state
+    bound: Vec<Vec<usize>>,
+    output: Output,
+}
+
+impl Transition {
+    // New state if the cell and its neighbours, arranged in the order of the
+    // transition, match it
+    fn apply(&self, states: &[u8]) -> Option<u8> {
+        let matches = self
+            .inputs
+            .iter()
+            .zip(states.iter())
+            .all(|(input, &state)| contains(input, state))
+            && self.bound.iter().all(|positions| {
+                positions
+                    .iter()
+                    .all(|&position| states[position] == states[positions[0]])
+            });
+        if !matches {
+            return None;
+        }
+        Some(match self.output {
+            Output::State(state) => state,
+            Output::Variable(variable) => states[self.bound[variable][0]],
+        })
+    }
+
+    // New state if the neighbours match the transition in any order, the
+    // states of the bound variables are tried one by one and the remaining
+    // neighbours are then matched with the remaining positions
+    fn apply_permuted(&self, states: u8, cell: u8, neighbours: &[u8]) -> Option<u8> {
+        if !contains(&self.inputs[0], cell) {
+            return None;
+        }
+        let mut values = Vec::with_capacity(self.bound.len());
+        self.bind(&mut values, states, cell, neighbours)
+    }
+
+    fn bind(&self, values: &mut Vec<u8>, states: u8, cell: u8, neighbours: &[u8]) -> Option<u8> {
+        let positions = match self.bound.get(values.len()) {
+            Some(positions) => positions,
+            None => return self.assign(values, neighbours),
+        };
+        for value in 0..states {
+            // Positions are ascending, so a variable of the cell starts with it
+            let fits =
+                contains(&self.inputs[positions[0]], value) && (positions[0] != 0 || value == cell);
+            if fits {
+                values.push(value);
+                let output = self.bind(values, states, cell, neighbours);
+                values.pop();
+                if output.is_some() {
+                    return output;
+                }
+            }
+        }
+        None
+    }
+
+    fn assign(&self, values: &[u8], neighbours: &[u8]) -> Option<u8> {
+        // Neighbours in the same state are interchangeable, so the bound
+        // positions can take any of them
+        let mut taken = [false; MAX_NEIGHBOURS];
+        for (positions, &value) in self.bound.iter().zip(values.iter()) {
+            for _ in positions.iter().filter(|&&position| position != 0) {
+                let i = (0..neighbours.len()).find(|&i| !taken[i] && neighbours[i] == value)?;
+                taken[i] = true;
+            }
+        }
+        let mut matched = [None; MAX_NEIGHBOURS];
+        for position in 1..self.inputs.len() {
+            if self
+                .bound
+                .iter()
+                .any(|positions| positions.contains(&position))
+            {
+                continue;
+            }
+            let mut visited = taken;
+            if !self.augment(position, neighbours, &mut matched, &mut visited) {
+                return None;
+            }
+        }
+        Some(match self.output {
+            Output::State(state) => state,
+            Output::Variable(variable) => values[variable],
+        })
+    }
+
+    // Matches `position` with a neighbour in one of its states, moving the
+    // positions matched before to other neighbours if needed
+    fn augment(
+        &self,
+        position: usize,
+        neighbours: &[u8],
+        matched: &mut [Option<usize>; MAX_NEIGHBOURS],
+        visited: &mut [bool; MAX_NEIGHBOURS],
+    ) -> bool {
+        for (i, &state) in neighbours.iter().enumerate() {
+            if visited[i] || !contains(&self.inputs[position], state) {
+                continue;
+            }
+            visited[i] = true;
+            let free = match matched[i] {
+                Some(other) => self.augment(other, neighbours, matched, visited),
+                None => true,
+            };
+            if free {
+                matched[i] = Some(position);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// State of the @TABLE section while it is being read
+struct TableParser {
+    states: Option<u8>,
+    neighbours: &'static [(i32, i32)],
+    // Name of the symmetry and its line, the permutations depend on the
+    // neighbourhood, so they are made once the whole table is read
+    symmetry: Option<(String, usize)>,
+    variables: HashMap<String, States>,
+    transitions: Vec<Transition>,
+}
+
+impl TableParser {
+    fn new() -> Self {
+        Self {
+            states: None,
+            neighbours: &MOORE,
+            symmetry: None,
+            variables: HashMap::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    fn states(&self) -> Result<u8, String> {
+        self.states
+            .ok_or_else(|| "'n_states' has to be given first".to_string())
+    }
+
+    fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        if let Some((key, value)) = line.split_once(':') {
+            return self.parse_setting(key.trim(), value.trim(), line_no);
+        }
+        match line.strip_prefix("var") {
+            Some(definition) if definition.starts_with(char::is_whitespace) => {
+                self.parse_variable(definition)
+            }
+            _ => self.parse_transition(line),
+        }
+    }
+
+    fn parse_setting(&mut self, key: &str, value: &str, line_no: usize) -> Result<(), String> {
+        match key {
+            "n_states" => {
+                let states = value
+                    .parse()
+                    .ok()
+                    .filter(|&states| states >= 2)
+                    .ok_or_else(|| format!("Number of states '{}' is out of range 2-255", value))?;
+                self.states = Some(states);
+            }
+            "neighborhood" => {
+                if !self.transitions.is_empty() {
+                    return Err("'neighborhood' has to be given before the transitions".into());
+                }
+                self.neighbours = match value.to_ascii_lowercase().as_str() {
+                    "moore" => &MOORE,
+                    "vonneumann" => &VON_NEUMANN,
+                    "hexagonal" => &HEXAGONAL,
+                    "onedimensional" => &ONE_DIMENSIONAL,
+                    _ => return Err(format!("Unknown neighborhood '{}'", value)),
+                };
+            }
+            "symmetries" => self.symmetry = Some((value.to_string(), line_no)),
+            _ => return Err(format!("Unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    // Symmetries are named after the rotations, e.g. rotate4 for turns
+    // by 90 degrees, optionally followed by "reflect"
+    fn symmetry(&self, name: &str) -> Result<Symmetry, String> {
+        let n = self.neighbours.len();
+        let mirror = |i: usize| (n - i) % n;
+        // Ordered west to east, so the mirror swaps them
+        let swap = |i: usize| 1 - i;
+        let one_dimensional = n == ONE_DIMENSIONAL.len();
+        let symmetry = match name {
+            "none" => permutations(n, n, None),
+            "permute" => Symmetry::Permute,
+            "reflect" if one_dimensional => permutations(n, n, Some(&swap)),
+            "reflect_horizontal" if !one_dimensional => permutations(n, n, Some(&mirror)),
+            _ => {
+                let rotations = name
+                    .strip_prefix("rotate")
+                    .filter(|_| !one_dimensional)
+                    .ok_or_else(|| format!("Unknown symmetry '{}'", name))?;
+                let (rotations, reflect) = match rotations.strip_suffix("reflect") {
+                    Some(rotations) => (rotations, true),
+                    None => (rotations, false),
+                };
+                let rotations: usize = rotations
+                    .parse()
+                    .ok()
+                    .filter(|&rotations| rotations > 0 && n.is_multiple_of(rotations))
+                    .ok_or_else(|| format!("Symmetry '{}' does not fit the neighborhood", name))?;
+                let mirror: &dyn Fn(usize) -> usize = &mirror;
+                permutations(n, n / rotations, Some(mirror).filter(|_| reflect))
+            }
+        };
+        Ok(symmetry)
+    }
+
+    fn parse_state(&self, text: &str) -> Result<u8, String> {
+        let states = self.states()?;
+        text.parse()
+            .ok()
+            .filter(|&state| state < states)
+            .ok_or_else(|| format!("State '{}' is out of range 0-{}", text, states - 1))
+    }
+
+    // var name={0,1,other_variable}
+    fn parse_variable(&mut self, definition: &str) -> Result<(), String> {
+        let (name, values) = definition
+            .split_once('=')
+            .ok_or_else(|| "Variable has to be defined as 'var name={...}'".to_string())?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("Invalid variable name '{}'", name));
+        }
+        let values = values
+            .trim()
+            .strip_prefix('{')
+            .and_then(|values| values.strip_suffix('}'))
+            .ok_or_else(|| "Values of a variable have to be enclosed in braces".to_string())?;
+        let mut states = [0; 4];
+        for value in values.split(',').map(str::trim) {
+            let value = match self.variables.get(value) {
+                Some(variable) => *variable,
+                None => single(self.parse_state(value)?),
+            };
+            for (states, value) in states.iter_mut().zip(value.iter()) {
+                *states |= value;
+            }
+        }
+        self.variables.insert(name.to_string(), states);
+        Ok(())
+    }
+
+    fn parse_entry(&self, text: &str) -> Result<Entry, String> {
+        if self.variables.contains_key(text) {
+            return Ok(Entry::Variable(text.to_string()));
+        }
+        if text.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Entry::State(self.parse_state(text)?));
+        }
+        Err(format!("Unknown variable '{}'", text))
+    }
+
+    fn parse_transition(&mut self, line: &str) -> Result<(), String> {
+        let mut texts: Vec<&str> = line.split(',').map(str::trim).collect();
+        // Single digit states can be written without commas
+        if texts.len() == 1 && line.chars().all(|c| c.is_ascii_digit()) {
+            texts = line
+                .char_indices()
+                .map(|(i, c)| &line[i..i + c.len_utf8()])
+                .collect();
+        }
+        let positions = self.neighbours.len() + 1;
+        if texts.len() != positions + 1 {
+            return Err(format!(
+                "Transition has to list {} states, found {}",
+                positions + 1,
+                texts.len()
+            ));
+        }
+        let entries = texts
+            .iter()
+            .map(|text| self.parse_entry(text))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Variables occurring more than once are bound to a single state
+        let mut occurrences: Vec<(&str, usize)> = Vec::new();
+        for entry in &entries {
+            if let Entry::Variable(name) = entry {
+                match occurrences.iter_mut().find(|(other, _)| other == name) {
+                    Some((_, count)) => *count += 1,
+                    None => occurrences.push((name, 1)),
+                }
+            }
+        }
+        let bound: Vec<&str> = occurrences
+            .iter()
+            .filter(|&&(_, count)| count > 1)
+            .map(|&(name, _)| name)
+            .collect();
+        let output = match &entries[positions] {
+            Entry::State(state) => Output::State(*state),
+            Entry::Variable(name) => match bound.iter().position(|bound| bound == name) {
+                Some(variable) => Output::Variable(variable),
+                None => return Err(format!("New state '{}' is not bound to any input", name)),
+            },
+        };
+        let inputs = entries[..positions]
+            .iter()
+            .map(|entry| match entry {
+                Entry::State(state) => single(*state),
+                Entry::Variable(name) => self.variables[name],
+            })
+            .collect();
+        let bound = bound
+            .iter()
+            .map(|&name| {
+                let entries = entries[..positions].iter().enumerate();
+                entries
+                    .filter(|(_, entry)| matches!(entry, Entry::Variable(other) if other == name))
+                    .map(|(position, _)| position)
+                    .collect()
+            })
+            .collect();
+        self.transitions.push(Transition {
+            inputs,
+            bound,
+            output,
+        });
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(Symmetry, Vec<Transition>), ParseError> {
+        let n = self.neighbours.len();
+        let symmetry = match &self.symmetry {
+            Some((name, line_no)) => self
+                .symmetry(name)
+                .map_err(|message| ParseError::new(*line_no, 1, message))?,
+            None => permutations(n, n, None),
+        };
+        Ok((symmetry, self.transitions))
+    }
+}
+
+// Color of a state, "state r g b", or a gradient from state 1 to the last
+// state, "r g b r g b"
+fn parse_color(
+    line: &str,
+    colors: &mut Vec<Option<(u8, u8, u8)>>,
+    states: u8,
+) -> Result<(), String> {
+    let values = line
+        .split_whitespace()
+        .map(|value| value.parse::<u8>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Invalid color '{}'", line))?;
+    colors.resize(usize::from(states), None);
+    match values[..] {
+        [state, r, g, b] if state < states => colors[usize::from(state)] = Some((r, g, b)),
+        [state, ..] if values.len() == 4 => {
+            return Err(format!(
+                "State '{}' is out of range 0-{}",
+                state,
+                states - 1
+            ))
+        }
+        [r1, g1, b1, r2, g2, b2] => {
+            let last = f64::from(states.max(3) - 2);
+            let mix = |from: u8, to: u8, ratio: f64| {
+                (f64::from(from) + (f64::from(to) - f64::from(from)) * ratio).round() as u8
+            };
+            for state in 1..states {
+                let ratio = f64::from(state - 1) / last;
+                colors[usize::from(state)] =
+                    Some((mix(r1, r2, ratio), mix(g1, g2, ratio), mix(b1, b2, ratio)));
+            }
+        }
+        _ => return Err(format!("Invalid color '{}'", line)),
+    }
+    Ok(())
+}
+
+// Cellular automaton given by a rule table, see the top of this file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    neighbours: Vec<(i32, i32)>,
+    symmetry: Symmetry,
+    transitions: Vec<Transition>,
+    // Bits of the transitions which match `state` at `position` (0 is the
+    // cell, neighbours follow) at index (position * states + state) * words
+    lookup: Vec<u64>,
+    // Bits of the transitions which match `state` at any neighbour at index
+    // state * words, used to narrow down permuted transitions
+    any_neighbour: Vec<u64>,
+    words: usize,
+    colors: Vec<Option<(u8, u8, u8)>>,
+}
+
+impl RuleTable {
+    pub fn load(path: &Path) -> Result<RuleTable, ParseRuleError> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            ParseRuleError::InvalidTable(format!("Cannot read '{}': {}", path.display(), e))
+        })?;
+        text.parse()
+            .map_err(|e| ParseRuleError::InvalidTable(format!("{}:{}", path.display(), e)))
+    }
+
+    // Directories searched for tables by name, those listed in the
+    // GAME_OF_LIFE_RULES variable, "rules" in the working directory and the
+    // one coming with the game
+    pub fn directories() -> Vec<PathBuf> {
+        let mut directories: Vec<PathBuf> = std::env::var_os("GAME_OF_LIFE_RULES")
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+        directories.push(PathBuf::from("rules"));
+        directories.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("rules"));
+        directories
+    }
+
+    // File of the table with the given @RULE name, "<name>.rule" in the
+    // first of the directories which has it
+    pub fn find(name: &str) -> Option<PathBuf> {
+        let file = format!("{}.rule", name);
+        Self::directories()
+            .iter()
+            .map(|directory| directory.join(&file))
+            .find(|path| path.is_file())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn next_state(&self, cell: u8, neighbours: &[u8]) -> u8 {
+        let states = usize::from(self.states);
+        if std::iter::once(&cell)
+            .chain(neighbours.iter())
+            .any(|&state| usize::from(state) >= states)
+        {
+            return cell;
+        }
+        for word in 0..self.words {
+            let mut candidates = self.candidates(word, cell, neighbours);
+            while candidates != 0 {
+                let transition =
+                    &self.transitions[word * 64 + candidates.trailing_zeros() as usize];
+                if let Some(state) = self.apply(transition, cell, neighbours) {
+                    return state;
+                }
+                candidates &= candidates - 1;
+            }
+        }
+        cell
+    }
+
+    // Bits of the transitions in `word` which the cell and its neighbours may
+    // match, bound variables aren't checked
+    fn candidates(&self, word: usize, cell: u8, neighbours: &[u8]) -> u64 {
+        let states = usize::from(self.states);
+        let bits = |position: usize, state: u8| {
+            self.lookup[(position * states + usize::from(state)) * self.words + word]
+        };
+        match &self.symmetry {
+            Symmetry::Permutations(permutations) => {
+                permutations.iter().fold(0, |candidates, permutation| {
+                    let arranged = permutation.iter().enumerate();
+                    candidates
+                        | arranged.fold(bits(0, cell), |candidates, (i, &j)| {
+                            candidates & bits(i + 1, neighbours[j])
+                        })
+                })
+            }
+            Symmetry::Permute => neighbours.iter().fold(bits(0, cell), |candidates, &state| {
+                candidates & self.any_neighbour[usize::from(state) * self.words + word]
+            }),
+        }
+    }
+
+    // New state if the transition or one of its symmetric ones matches
+    fn apply(&self, transition: &Transition, cell: u8, neighbours: &[u8]) -> Option<u8> {
+        // Candidates without bound variables already match one of the
+        // arrangements
+        if let (Symmetry::Permutations(_), Output::State(state)) =
+            (&self.symmetry, transition.output)
+        {
+            if transition.bound.is_empty() {
+                return Some(state);
+            }
+        }
+        match &self.symmetry {
+            Symmetry::Permutations(permutations) => permutations.iter().find_map(|permutation| {
+                // Entry `i` of the transition is matched with neighbour `j`
+                let mut states = [cell; MAX_NEIGHBOURS + 1];
+                for (i, &j) in permutation.iter().enumerate() {
+                    states[i + 1] = neighbours[j];
+                }
+                transition.apply(&states[..=neighbours.len()])
+            }),
+            Symmetry::Permute => transition.apply_permuted(self.states, cell, neighbours),
+        }
+    }
+
+    // Writes next states of `rows` to `out` row by row
+    fn next_rows(&self, board: &Board, rows: std::ops::Range<usize>, out: &mut [u8]) {
+        let mut neighbours = vec![0; self.neighbours.len()];
+        let cells = rows.flat_map(|y| (0..board.width()).map(move |x| (x as i32, y as i32)));
+        for ((x, y), out) in cells.zip(out.iter_mut()) {
+            for (neighbour, &(dx, dy)) in neighbours.iter_mut().zip(self.neighbours.iter()) {
+                *neighbour = board.get_state_wrapped(x + dx, y + dy);
+            }
+            let cell = board.get_state(x, y).expect("Internal logic error");
+            *out = self.next_state(cell, &neighbours);
+        }
+    }
+
+    fn create_board(&self, board: &Board, states: &[u8]) -> Board {
+        let mut next = Board::create(board.width(), board.height()).with_topology(board.topology);
+        if self.states > 2 {
+            next.enable_states();
+        }
+        let cells = (0..board.height()).flat_map(|y| (0..board.width()).map(move |x| (x, y)));
+        for ((x, y), &state) in cells.zip(states.iter()) {
+            if state != 0 {
+                next.set_state(x, y, state);
+            }
+        }
+        next
+    }
+}
+
+impl std::str::FromStr for RuleTable {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut section = String::new();
+        let mut parser = None;
+        let mut colors = Vec::new();
+        for (line_no, line) in (1..).zip(s.lines()) {
+            let error = |message: String| ParseError::new(line_no, 1, message);
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('@') {
+                let (header, rest) = header
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((header, ""));
+                section = header.to_ascii_uppercase();
+                match section.as_str() {
+                    "RULE" => name = Some(rest.trim().to_string()),
+                    "TABLE" if parser.is_none() => parser = Some(TableParser::new()),
+                    "TABLE" => return Err(error("Section '@TABLE' is listed twice".into())),
+                    _ => {}
+                }
+                continue;
+            }
+            match section.as_str() {
+                "TABLE" => parser
+                    .as_mut()
+                    .expect("Internal logic error")
+                    .parse_line(line, line_no)
+                    .map_err(error)?,
+                "COLORS" => {
+                    let states = match &parser {
+                        Some(parser) => parser.states().map_err(error)?,
+                        None => return Err(error("'@COLORS' has to follow '@TABLE'".into())),
+                    };
+                    parse_color(line, &mut colors, states).map_err(error)?
+                }
+                // Descriptions of the rule and unsupported sections
+                _ => {}
+            }
+        }
+
+        let missing = |what: &str| ParseError::new(1, 1, format!("Missing {}", what));
+        let name = name.ok_or_else(|| missing("'@RULE' section"))?;
+        let parser = parser.ok_or_else(|| missing("'@TABLE' section"))?;
+        let states = parser.states().map_err(|_| missing("'n_states'"))?;
+        let neighbours = parser.neighbours.to_vec();
+        let positions = neighbours.len() + 1;
+        let (symmetry, transitions) = parser.finish()?;
+        let words = transitions.len().div_ceil(64).max(1);
+        let mut lookup = vec![0; positions * usize::from(states) * words];
+        let mut any_neighbour = vec![0; usize::from(states) * words];
+        for (i, transition) in transitions.iter().enumerate() {
+            let bit = 1 << (i % 64);
+            for (position, input) in transition.inputs.iter().enumerate() {
+                for state in (0..states).filter(|&state| contains(input, state)) {
+                    let index = (position * usize::from(states) + usize::from(state)) * words;
+                    lookup[index + i / 64] |= bit;
+                    if position > 0 {
+                        any_neighbour[usize::from(state) * words + i / 64] |= bit;
+                    }
+                }
+            }
+        }
+        colors.resize(usize::from(states), None);
+        Ok(RuleTable {
+            name,
+            states,
+            neighbours,
+            symmetry,
+            transitions,
+            lookup,
+            any_neighbour,
+            words,
+            colors,
+        })
+    }
+}
+
+impl std::fmt::Display for RuleTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// Sparse boards hold only live cells, so they can't run tables with more
+// than two states
+impl Rule for RuleTable {
+    fn next_board(&mut self, board: &Board) -> Board {
+        let mut states = vec![0; board.width() * board.height()];
+        self.next_rows(board, 0..board.height(), &mut states);
+        self.create_board(board, &states)
+    }
+
+    fn next_board_parallel(&mut self, board: &Board, pool: &ThreadPool) -> Board {
+        let mut states = vec![0; board.width() * board.height()];
+        let rule = &*self;
+        pool.for_each_band(&mut states, board.width(), |rows, band| {
+            rule.next_rows(board, rows, band)
+        });
+        self.create_board(board, &states)
+    }
+
+    fn notation(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn states(&self) -> u8 {
+        self.states
+    }
+
+    fn hexagonal(&self) -> bool {
+        self.neighbours[..] == HEXAGONAL[..]
+    }
+
+    fn state_color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        self.colors.get(usize::from(state)).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(settings: &str, transitions: &str) -> RuleTable {
+        let text = format!("@RULE Test\n@TABLE\n{}\n{}\n", settings, transitions);
+        text.parse().unwrap_or_else(|e| panic!("{}\n{}", text, e))
+    }
+
+    #[test]
+    fn binds_repeated_variables() {
+        let rule = table(
+            "n_states:3\nneighborhood:vonNeumann\nsymmetries:none\nvar a={1,2}\nvar b={0,1,2}\nvar c={0,1,2}",
+            "0,a,a,b,c,a",
+        );
+        assert_eq!(rule.next_state(0, &[1, 1, 0, 2]), 1);
+        assert_eq!(rule.next_state(0, &[2, 2, 1, 0]), 2);
+        assert_eq!(rule.next_state(0, &[1, 2, 0, 0]), 0);
+        assert!(
+            "@RULE Test\n@TABLE\nn_states:2\nvar a={0,1}\n0,0,0,0,0,0,0,0,0,a\n"
+                .parse::<RuleTable>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn applies_rotations_and_reflections() {
+        // North and north-east neighbours alive
+        let transition = "0,1,1,0,0,0,0,0,0,1";
+        let rotate = table("n_states:2\nsymmetries:rotate4", transition);
+        // Neighborhood given after the symmetry keeps it
+        let reflect = table(
+            "n_states:2\nsymmetries:rotate4reflect\nneighborhood:Moore",
+            transition,
+        );
+        for rule in &[&rotate, &reflect] {
+            assert_eq!(rule.next_state(0, &[1, 1, 0, 0, 0, 0, 0, 0]), 1);
+            assert_eq!(rule.next_state(0, &[0, 0, 0, 0, 1, 1, 0, 0]), 1);
+            assert_eq!(rule.next_state(0, &[1, 0, 1, 0, 0, 0, 0, 0]), 0);
+        }
+        // Mirror image, north and north-west
+        assert_eq!(rotate.next_state(0, &[1, 0, 0, 0, 0, 0, 0, 1]), 0);
+        assert_eq!(reflect.next_state(0, &[1, 0, 0, 0, 0, 0, 0, 1]), 1);
+    }
+
+    #[test]
+    fn permutes_neighbours() {
+        // Eight different states in any order, which would be 8! transitions
+        // if they were listed
+        let variables: String = (1..=8)
+            .map(|i| format!("var v{}={{1,2,3,4,5,6,7,8}}\n", i))
+            .collect();
+        let rule = table(
+            &format!("n_states:9\nsymmetries:permute\n{}", variables),
+            "0,1,2,3,4,5,6,7,8,1\n0,v1,v2,v3,v4,v5,v6,v7,v8,2\n0,1,1,0,0,0,0,0,0,3",
+        );
+        assert_eq!(rule.transitions.len(), 3);
+        assert_eq!(rule.next_state(0, &[8, 3, 5, 1, 7, 2, 6, 4]), 1);
+        assert_eq!(rule.next_state(0, &[8, 8, 5, 1, 7, 2, 6, 4]), 2);
+        assert_eq!(rule.next_state(0, &[0, 0, 1, 0, 0, 0, 1, 0]), 3);
+        assert_eq!(rule.next_state(0, &[0, 0, 1, 0, 0, 0, 2, 0]), 0);
+        assert_eq!(rule.next_state(1, &[8, 3, 5, 1, 7, 2, 6, 4]), 1);
+    }
+
+    #[test]
+    fn binds_permuted_variables() {
+        // Two neighbours in the same state as the cell and two other ones in
+        // one state, which becomes the new state
+        let rule = table(
+            "n_states:4\nneighborhood:vonNeumann\nsymmetries:permute\nvar a={1,2,3}\nvar b={1,2,3}\nvar c={0,1}",
+            "a,b,b,a,a,b",
+        );
+        assert_eq!(rule.next_state(1, &[2, 1, 1, 2]), 2);
+        assert_eq!(rule.next_state(3, &[3, 1, 3, 1]), 1);
+        assert_eq!(rule.next_state(1, &[1, 1, 1, 1]), 1);
+        assert_eq!(rule.next_state(1, &[2, 1, 3, 2]), 1);
+        assert_eq!(rule.next_state(0, &[2, 2, 0, 0]), 0);
+        // Unbound variables take any remaining neighbour
+        let rule = table(
+            "n_states:4\nneighborhood:vonNeumann\nsymmetries:permute\nvar a={1,2}\nvar b={0,1}\nvar c={0,1}",
+            "0,a,a,b,c,a",
+        );
+        assert_eq!(rule.next_state(0, &[0, 2, 1, 2]), 2);
+        assert_eq!(rule.next_state(0, &[1, 0, 1, 1]), 1);
+        assert_eq!(rule.next_state(0, &[1, 1, 0, 2]), 0);
+        assert_eq!(rule.next_state(0, &[2, 2, 2, 1]), 0);
+        assert_eq!(rule.next_state(0, &[1, 1, 3, 0]), 0);
+    }
+
+    #[test]
+    fn reads_hexagonal_tables() {
+        let rule = table(
+            "n_states:2\nneighborhood:hexagonal\nsymmetries:rotate6",
+            "01000001",
+        );
+        assert!(rule.hexagonal());
+        for i in 0..6 {
+            let mut neighbours = [0; 6];
+            neighbours[i] = 1;
+            assert_eq!(rule.next_state(0, &neighbours), 1);
+        }
+        assert_eq!(rule.next_state(0, &[1, 1, 0, 0, 0, 0]), 0);
+    }
+
+    #[test]
+    fn reads_colors() {
+        let rule = table(
+            "n_states:5",
+            "0,0,0,0,0,0,0,0,0,0\n@COLORS\n255 0 0 0 0 255\n0 1 2 3",
+        );
+        assert_eq!(rule.state_color(0), Some((1, 2, 3)));
+        assert_eq!(rule.state_color(1), Some((255, 0, 0)));
+        assert_eq!(rule.state_color(2), Some((170, 0, 85)));
+        assert_eq!(rule.state_color(4), Some((0, 0, 255)));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |text: &str| text.parse::<RuleTable>().unwrap_err();
+        let non_ascii = error("@RULE Test\n@TABLE\nn_states:2\n0é1\n");
+        assert_eq!(non_ascii.line, 4);
+        let symmetry = error("@RULE Test\n@TABLE\nn_states:2\nsymmetries:rotate3\n");
+        assert_eq!(symmetry.line, 4);
+        assert_eq!(error("@TABLE\nn_states:2\n").line, 1);
+    }
+
+    #[test]
+    fn runs_wireworld() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("rules/WireWorld.rule");
+        let rule = RuleTable::load(&path).unwrap();
+        assert_eq!(rule.states(), 4);
+        // Wires next to one or two heads become heads
+        assert_eq!(rule.next_state(3, &[1, 0, 0, 0, 0, 0, 0, 0]), 1);
+        assert_eq!(rule.next_state(3, &[0, 0, 1, 3, 0, 0, 0, 1]), 1);
+        assert_eq!(rule.next_state(3, &[1, 1, 1, 0, 0, 0, 0, 0]), 3);
+        assert_eq!(rule.next_state(1, &[3; 8]), 2);
+        assert_eq!(rule.next_state(2, &[1; 8]), 3);
+        assert_eq!(rule.next_state(0, &[1; 8]), 0);
+    }
+}
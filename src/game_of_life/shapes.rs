@@ -29,13 +29,50 @@ impl Shape {
     pub fn new(shape: Vec<(i32, i32)>) -> Shape {
         Shape::new_opt(shape).unwrap_or_default()
     }
+
+    pub fn from_board(board: &super::Board) -> Shape {
+        let mut points: Vec<_> = board
+            .indexed_iter()
+            .filter(|(&cell, _)| cell)
+            .map(|(_, pos)| pos)
+            .collect();
+        points.sort_by_key(|&(x, y)| (y, x));
+        Shape::new(points)
+    }
+
+    // Cells in any other state than dead, see `Board::get_state`
+    pub fn from_board_states(board: &super::Board) -> Shape {
+        let points = (0..board.height() as i32)
+            .flat_map(|y| (0..board.width() as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| board.get_state(x, y) != Some(0))
+            .collect();
+        Shape::new(points)
+    }
+
+    // Points relative to the top left corner of the shape
+    pub fn points(&self) -> &[(i32, i32)] {
+        &self.shape
+    }
+
+    pub fn pos(&self) -> (i32, i32) {
+        self.pos
+    }
+
+    // Width and height of the bounding box
+    pub fn dimensions(&self) -> (i32, i32) {
+        if self.shape.is_empty() {
+            (0, 0)
+        } else {
+            (self.size.0 + 1, self.size.1 + 1)
+        }
+    }
 }
 
 impl Shape {
     fn center(&self) -> (i32, i32) {
         (self.pos.0 - self.size.0 / 2, self.pos.1 - self.size.1 / 2)
     }
-    fn center_at_pos(&self, pos: (i32, i32)) -> (i32, i32) {
+    pub(super) fn center_at_pos(&self, pos: (i32, i32)) -> (i32, i32) {
         let center = self.center();
         (center.0 + pos.0, center.1 + pos.1)
     }
@@ -0,0 +1,185 @@
+// Text format capturing the whole game, e.g.:
+//   #Game of life snapshot
+//   width = 100
+//   height = 75
+//   topology = torus
+//   rule = B3/S23
+//   generation = 42
+//   cells:
+//   x = 3, y = 3, rule = B3/S23
+//   bo$2bo$3o!
+// Any other `key = value` lines are kept as extra fields.
+
+use std::collections::HashMap;
+
+use super::formats::{self, rle};
+use super::{board, rules, Game, Topology};
+
+const HEADER: &str = "#Game of life snapshot";
+const CELLS: &str = "cells:";
+
+#[derive(Debug)]
+pub enum Error {
+    MissingHeader,
+    MissingField(&'static str),
+    InvalidValue { field: String, value: String },
+    UnnamedRule,
+    Rule(rules::ParseRuleError),
+    Cells(formats::ParseError),
+    CellOutsideBoard(i32, i32),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "Snapshot has to start with '{}'", HEADER),
+            Self::MissingField(field) => write!(f, "Snapshot is missing field '{}'", field),
+            Self::InvalidValue { field, value } => {
+                write!(f, "Invalid value '{}' of field '{}'", value, field)
+            }
+            Self::UnnamedRule => write!(f, "Rule without rule string cannot be saved"),
+            Self::Rule(e) => write!(f, "Invalid rule in snapshot ({})", e),
+            Self::Cells(e) => write!(f, "Invalid cells in snapshot ({})", e),
+            Self::CellOutsideBoard(x, y) => {
+                write!(f, "Cell ({}, {}) lies outside of the board", x, y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub fn write(game: &Game, extra: &[(&str, String)]) -> Result<String> {
+    let rule = game.rule.notation().ok_or(Error::UnnamedRule)?;
+    let mut out = format!("{}\n", HEADER);
+    out += &format!("width = {}\n", game.board.width());
+    out += &format!("height = {}\n", game.board.height());
+    out += &format!("topology = {}\n", game.board.topology());
+    out += &format!("rule = {}\n", rule);
+    out += &format!("generation = {}\n", game.generation);
+    for (key, value) in extra {
+        out += &format!("{} = {}\n", key, value);
+    }
+    out += &format!("{}\n", CELLS);
+    // Dying cells of rules with more than two states are kept as well
+    out += &rle::write(&formats::Pattern::from_board(&game.board));
+    Ok(out)
+}
+
+fn parse_field<T: std::str::FromStr>(
+    fields: &mut HashMap<String, String>,
+    field: &'static str,
+) -> Result<T> {
+    let value = fields.remove(field).ok_or(Error::MissingField(field))?;
+    value.parse().map_err(|_| Error::InvalidValue {
+        field: field.into(),
+        value,
+    })
+}
+
+// Board dimension between 1 and `board::MAX_SIZE`
+fn parse_size(fields: &mut HashMap<String, String>, field: &'static str) -> Result<usize> {
+    let size: usize = parse_field(fields, field)?;
+    if size == 0 || size > board::MAX_SIZE {
+        return Err(Error::InvalidValue {
+            field: field.into(),
+            value: size.to_string(),
+        });
+    }
+    Ok(size)
+}
+
+// Returns the game together with the extra fields
+pub fn read(input: &str) -> Result<(Game<'static>, HashMap<String, String>)> {
+    let mut lines = input.lines();
+    if lines.next().map(str::trim_end) != Some(HEADER) {
+        return Err(Error::MissingHeader);
+    }
+    let mut fields = HashMap::new();
+    for line in &mut lines {
+        let line = line.trim();
+        if line == CELLS {
+            break;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            fields.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    let cells: Vec<_> = lines.collect();
+
+    let width = parse_size(&mut fields, "width")?;
+    let height = parse_size(&mut fields, "height")?;
+    let topology: Topology = parse_field(&mut fields, "topology")?;
+    let rule = fields.remove("rule").ok_or(Error::MissingField("rule"))?;
+    let rule = rules::parse(&rule).map_err(Error::Rule)?;
+    let generation = parse_field(&mut fields, "generation")?;
+
+    let mut game = Game::create_boxed(width, height, rule).with_topology(topology);
+    game.generation = generation;
+    let pattern = rle::parse(&cells.join("\n")).map_err(Error::Cells)?;
+    let (pos_x, pos_y) = pattern.shape.pos();
+    for &(x, y) in pattern.shape.points() {
+        let state = pattern.state((x, y));
+        let (x, y) = (pos_x + x, pos_y + y);
+        game.board
+            .set_state(x, y, state)
+            .ok_or(Error::CellOutsideBoard(x, y))?;
+    }
+    Ok((game, fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let rule = rules::parse("345/2/4").unwrap();
+        let mut game = Game::create_boxed(70, 5, rule).with_topology(Topology::KleinBottle);
+        game.generation = 42;
+        game.board.set_state(0, 0, 1);
+        game.board.set_state(69, 4, 2);
+        game.board.set_state(35, 2, 3);
+        let text = write(&game, &[("speed", "4".into())]).unwrap();
+        let (read, fields) = read(&text).unwrap();
+        assert_eq!(read.board, game.board);
+        assert_eq!(read.board.topology(), Topology::KleinBottle);
+        assert_eq!(read.generation, 42);
+        assert_eq!(read.rule.notation(), game.rule.notation());
+        assert_eq!(read.rule.states(), 4);
+        assert_eq!(fields.get("speed").map(String::as_str), Some("4"));
+        assert_eq!(fields.len(), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_snapshots() {
+        let snapshot = |size: &str, cells: &str| {
+            format!(
+                "{}\n{}\ntopology = torus\nrule = B3/S23\ngeneration = 0\ncells:\n{}",
+                HEADER, size, cells
+            )
+        };
+        let cells = "x = 3, y = 1\n3o!\n";
+        assert!(read(&snapshot("width = 3\nheight = 1", cells)).is_ok());
+        assert!(matches!(
+            read(&snapshot("width = 3\nheight = 0", cells)),
+            Err(Error::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            read(&snapshot("width = 2\nheight = 1", cells)),
+            Err(Error::CellOutsideBoard(2, 0))
+        ));
+        let cells = "#CXRLE Pos=0,-1\nx = 3, y = 1\n3o!\n";
+        assert!(matches!(
+            read(&snapshot("width = 3\nheight = 1", cells)),
+            Err(Error::CellOutsideBoard(0, -1))
+        ));
+        assert!(matches!(
+            read(&snapshot("height = 1", cells)),
+            Err(Error::MissingField("width"))
+        ));
+        assert!(matches!(read("x = 3"), Err(Error::MissingHeader)));
+    }
+}
@@ -0,0 +1,345 @@
+// Random soups with symmetries used by soup searchers, named as in apgsearch,
+// the number after the symmetry group tells whether its centre lies
+// in the middle of a cell (1), an edge (2) or a vertex (4)
+
+use std::convert::TryFrom;
+
+use super::Board;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Symmetry {
+    // No symmetry
+    #[default]
+    C1,
+    // Rotation by 180 degrees
+    C2_1,
+    C2_2,
+    C2_4,
+    // Rotation by 90 degrees
+    C4_1,
+    C4_4,
+    // Reflection along horizontal axis
+    D2Plus1,
+    D2Plus2,
+    // Reflection along diagonal
+    D2X,
+    // Reflections along horizontal and vertical axis
+    D4Plus1,
+    D4Plus2,
+    D4Plus4,
+    // Reflections along both diagonals
+    D4X1,
+    D4X4,
+    // All rotations and reflections of a square
+    D8_1,
+    D8_4,
+}
+
+// Maps cell of a soup of given size to its image
+#[derive(Debug, Clone, Copy)]
+enum Transform {
+    Rotate90,
+    Rotate180,
+    FlipX,
+    FlipY,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Transform {
+    fn apply(self, (width, height): (usize, usize), (x, y): (usize, usize)) -> (usize, usize) {
+        match self {
+            // Only used on square soups
+            Self::Rotate90 => (height - 1 - y, x),
+            Self::Rotate180 => (width - 1 - x, height - 1 - y),
+            Self::FlipX => (width - 1 - x, y),
+            Self::FlipY => (x, height - 1 - y),
+            Self::Transpose => (y, x),
+            Self::AntiTranspose => (height - 1 - y, width - 1 - x),
+        }
+    }
+}
+
+fn odd(n: usize) -> usize {
+    n - (1 - n % 2)
+}
+
+fn even(n: usize) -> usize {
+    n - n % 2
+}
+
+impl Symmetry {
+    pub fn all() -> Vec<Symmetry> {
+        vec![
+            Self::C1,
+            Self::C2_1,
+            Self::C2_2,
+            Self::C2_4,
+            Self::C4_1,
+            Self::C4_4,
+            Self::D2Plus1,
+            Self::D2Plus2,
+            Self::D2X,
+            Self::D4Plus1,
+            Self::D4Plus2,
+            Self::D4Plus4,
+            Self::D4X1,
+            Self::D4X4,
+            Self::D8_1,
+            Self::D8_4,
+        ]
+    }
+
+    // Width and height of a soup which fits into `size` x `size` square,
+    // the parity of the sides gives the position of the centre
+    pub fn dimensions(&self, size: usize) -> (usize, usize) {
+        if size == 0 {
+            return (0, 0);
+        }
+        match self {
+            Self::C1 | Self::D2X => (size, size),
+            Self::C2_1 | Self::C4_1 | Self::D4Plus1 | Self::D4X1 | Self::D8_1 => {
+                (odd(size), odd(size))
+            }
+            Self::C2_2 | Self::D4Plus2 => (odd(size), even(size)),
+            Self::C2_4 | Self::C4_4 | Self::D4Plus4 | Self::D4X4 | Self::D8_4 => {
+                (even(size), even(size))
+            }
+            Self::D2Plus1 => (size, odd(size)),
+            Self::D2Plus2 => (size, even(size)),
+        }
+    }
+
+    fn generators(&self) -> &'static [Transform] {
+        use Transform::*;
+        match self {
+            Self::C1 => &[],
+            Self::C2_1 | Self::C2_2 | Self::C2_4 => &[Rotate180],
+            Self::C4_1 | Self::C4_4 => &[Rotate90],
+            Self::D2Plus1 | Self::D2Plus2 => &[FlipY],
+            Self::D2X => &[Transpose],
+            Self::D4Plus1 | Self::D4Plus2 | Self::D4Plus4 => &[FlipX, FlipY],
+            Self::D4X1 | Self::D4X4 => &[Transpose, AntiTranspose],
+            Self::D8_1 | Self::D8_4 => &[Rotate90, Transpose],
+        }
+    }
+
+    // Cells which have to be in the same state as the given one
+    fn orbit(&self, size: (usize, usize), cell: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut orbit = vec![cell];
+        let mut i = 0;
+        while i < orbit.len() {
+            for transform in self.generators() {
+                let image = transform.apply(size, orbit[i]);
+                if !orbit.contains(&image) {
+                    orbit.push(image);
+                }
+            }
+            i += 1;
+        }
+        orbit
+    }
+}
+
+impl Board {
+    // Fills soup of `symmetry.dimensions(size)` cells with top left corner
+    // at `pos`, cells outside of it are left untouched, fails if it doesn't
+    // fit the board, same arguments always give the same soup
+    pub fn add_soup(
+        &mut self,
+        seed: u64,
+        density: f64,
+        symmetry: Symmetry,
+        pos: (i32, i32),
+        size: usize,
+    ) -> Option<()> {
+        use rand::{Rng, SeedableRng};
+        let (width, height) = symmetry.dimensions(size);
+        let mut soup = Board::create(width, height);
+        let mut done = Board::create(width, height);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let density = density.clamp(0.0, 1.0);
+        for y in 0..height {
+            for x in 0..width {
+                if done.get(x, y) == Some(&true) {
+                    continue;
+                }
+                let cell = rng.gen_bool(density);
+                for (x, y) in symmetry.orbit((width, height), (x, y)) {
+                    soup.set(x, y, cell);
+                    done.set(x, y, true);
+                }
+            }
+        }
+
+        let x = usize::try_from(pos.0).ok()?;
+        let y = usize::try_from(pos.1).ok()?;
+        if x.checked_add(width)? > self.width || y.checked_add(height)? > self.height {
+            return None;
+        }
+        for (&cell, (soup_x, soup_y)) in soup.indexed_iter() {
+            self.set(x + soup_x as usize, y + soup_y as usize, cell);
+        }
+        Some(())
+    }
+}
+
+impl std::fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::C1 => "C1",
+            Self::C2_1 => "C2_1",
+            Self::C2_2 => "C2_2",
+            Self::C2_4 => "C2_4",
+            Self::C4_1 => "C4_1",
+            Self::C4_4 => "C4_4",
+            Self::D2Plus1 => "D2_+1",
+            Self::D2Plus2 => "D2_+2",
+            Self::D2X => "D2_x",
+            Self::D4Plus1 => "D4_+1",
+            Self::D4Plus2 => "D4_+2",
+            Self::D4Plus4 => "D4_+4",
+            Self::D4X1 => "D4_x1",
+            Self::D4X4 => "D4_x4",
+            Self::D8_1 => "D8_1",
+            Self::D8_4 => "D8_4",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSymmetryError(pub String);
+
+impl std::fmt::Display for ParseSymmetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = Symmetry::all().iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "Unknown symmetry '{}' (expected one of: {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseSymmetryError {}
+
+impl std::str::FromStr for Symmetry {
+    type Err = ParseSymmetryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Symmetry::all()
+            .into_iter()
+            .find(|symmetry| symmetry.to_string().to_ascii_lowercase() == name)
+            .ok_or_else(|| ParseSymmetryError(s.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every transform of the symmetry group other than identity, not just
+    // its generators
+    fn transforms(symmetry: Symmetry) -> Vec<Transform> {
+        use Symmetry::*;
+        use Transform::*;
+        match symmetry {
+            C1 => vec![],
+            C2_1 | C2_2 | C2_4 => vec![Rotate180],
+            C4_1 | C4_4 => vec![Rotate90, Rotate180],
+            D2Plus1 | D2Plus2 => vec![FlipY],
+            D2X => vec![Transpose],
+            D4Plus1 | D4Plus2 | D4Plus4 => vec![FlipX, FlipY, Rotate180],
+            D4X1 | D4X4 => vec![Transpose, AntiTranspose, Rotate180],
+            D8_1 | D8_4 => vec![Rotate90, Rotate180, FlipX, FlipY, Transpose, AntiTranspose],
+        }
+    }
+
+    fn soup(symmetry: Symmetry, seed: u64, size: usize) -> Board {
+        let (width, height) = symmetry.dimensions(size);
+        let mut board = Board::create(width, height);
+        board.add_soup(seed, 0.5, symmetry, (0, 0), size).unwrap();
+        board
+    }
+
+    #[test]
+    fn soups_are_invariant_under_their_symmetry() {
+        for symmetry in Symmetry::all() {
+            for size in 1..=9 {
+                for seed in 0..4 {
+                    let board = soup(symmetry, seed, size);
+                    let dimensions = (board.width(), board.height());
+                    for (&cell, (x, y)) in board.indexed_iter() {
+                        for transform in transforms(symmetry) {
+                            let (image_x, image_y) =
+                                transform.apply(dimensions, (x as usize, y as usize));
+                            assert_eq!(
+                                board.get(image_x, image_y),
+                                Some(&cell),
+                                "{} size {} seed {} {:?} of ({}, {})",
+                                symmetry,
+                                size,
+                                seed,
+                                transform,
+                                x,
+                                y
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_soup() {
+        for symmetry in Symmetry::all() {
+            assert_eq!(soup(symmetry, 5, 16), soup(symmetry, 5, 16), "{}", symmetry);
+        }
+        assert_ne!(soup(Symmetry::C1, 5, 16), soup(Symmetry::C1, 6, 16));
+    }
+
+    #[test]
+    fn dimensions_give_centre() {
+        let cases = [
+            (Symmetry::C1, (16, 16)),
+            (Symmetry::C2_1, (15, 15)),
+            (Symmetry::C2_2, (15, 16)),
+            (Symmetry::C2_4, (16, 16)),
+            (Symmetry::D2Plus1, (16, 15)),
+            (Symmetry::D8_1, (15, 15)),
+        ];
+        for &(symmetry, dimensions) in &cases {
+            assert_eq!(symmetry.dimensions(16), dimensions, "{}", symmetry);
+        }
+        assert_eq!(Symmetry::D8_4.dimensions(0), (0, 0));
+        assert_eq!(Symmetry::D8_4.dimensions(1), (0, 0));
+    }
+
+    #[test]
+    fn soup_fits_the_board() {
+        let mut board = Board::create(20, 20);
+        board.set(0, 0, true);
+        board
+            .add_soup(1, 1.0, Symmetry::D4Plus2, (2, 4), 16)
+            .unwrap();
+        for (&cell, (x, y)) in board.indexed_iter() {
+            let inside = (2..17).contains(&x) && (4..20).contains(&y);
+            assert_eq!(cell, inside || (x, y) == (0, 0), "{} {}", x, y);
+        }
+        assert_eq!(board.add_soup(1, 0.5, Symmetry::C1, (5, 5), 16), None);
+        assert_eq!(board.add_soup(1, 0.5, Symmetry::C1, (-1, 0), 4), None);
+    }
+
+    #[test]
+    fn parses_own_names() {
+        for symmetry in Symmetry::all() {
+            assert_eq!(symmetry.to_string().parse(), Ok(symmetry));
+        }
+        assert_eq!("d4_X1".parse(), Ok(Symmetry::D4X1));
+        assert!("D3".parse::<Symmetry>().is_err());
+    }
+}
@@ -0,0 +1,261 @@
+// Unbounded board made of square tiles, which are allocated only while
+// they contain live cells
+
+use std::collections::{HashMap, HashSet};
+
+use super::Board;
+
+pub const TILE_SIZE: i64 = 64;
+
+// Row by row, bit x of row y is cell (x, y) of the tile
+type Tile = [u64; TILE_SIZE as usize];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseBoard {
+    tiles: HashMap<(i64, i64), Tile>,
+}
+
+fn tile_pos(x: i64, y: i64) -> ((i64, i64), usize, u64) {
+    let tile = (x.div_euclid(TILE_SIZE), y.div_euclid(TILE_SIZE));
+    let row = y.rem_euclid(TILE_SIZE) as usize;
+    let mask = 1 << x.rem_euclid(TILE_SIZE);
+    (tile, row, mask)
+}
+
+fn is_empty(tile: &Tile) -> bool {
+    tile.iter().all(|&row| row == 0)
+}
+
+impl SparseBoard {
+    pub fn create() -> SparseBoard {
+        SparseBoard::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let (tile, row, mask) = tile_pos(x, y);
+        self.tiles
+            .get(&tile)
+            .is_some_and(|tile| tile[row] & mask != 0)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, value: bool) {
+        let (tile_pos, row, mask) = tile_pos(x, y);
+        if value {
+            self.tiles
+                .entry(tile_pos)
+                .or_insert([0; TILE_SIZE as usize])[row] |= mask;
+        } else if let Some(tile) = self.tiles.get_mut(&tile_pos) {
+            tile[row] &= !mask;
+            if is_empty(tile) {
+                self.tiles.remove(&tile_pos);
+            }
+        }
+    }
+
+    pub fn toggle(&mut self, x: i64, y: i64) {
+        let value = self.get(x, y);
+        self.set(x, y, !value);
+    }
+
+    pub fn population(&self) -> usize {
+        self.tiles
+            .values()
+            .flat_map(|tile| tile.iter())
+            .map(|row| row.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn indexed_iter(&self) -> impl '_ + Iterator<Item = (i64, i64)> {
+        self.tiles.iter().flat_map(|(&(tile_x, tile_y), tile)| {
+            (0..TILE_SIZE).flat_map(move |y| {
+                let row = tile[y as usize];
+                (0..TILE_SIZE)
+                    .filter(move |x| row & (1 << x) != 0)
+                    .map(move |x| (tile_x * TILE_SIZE + x, tile_y * TILE_SIZE + y))
+            })
+        })
+    }
+
+    // Smallest rectangle [from, to) containing all live cells
+    pub fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))> {
+        let mut cells = self.indexed_iter();
+        let first = cells.next()?;
+        let (from, to) = cells.fold((first, first), |(from, to), (x, y)| {
+            ((from.0.min(x), from.1.min(y)), (to.0.max(x), to.1.max(y)))
+        });
+        Some((from, (to.0 + 1, to.1 + 1)))
+    }
+
+    // Sets cells of the sparse board from board placed at `pos`
+    pub fn read_board(&mut self, board: &Board, pos: (i64, i64)) {
+        for (&cell, (x, y)) in board.indexed_iter() {
+            self.set(pos.0 + i64::from(x), pos.1 + i64::from(y), cell);
+        }
+    }
+
+    // Copies region starting at `pos` into the board
+    pub fn write_board(&self, board: &mut Board, pos: (i64, i64)) {
+        board.clear();
+        let (from, _, _) = tile_pos(pos.0, pos.1);
+        let (to, _, _) = tile_pos(
+            pos.0 + board.width() as i64 - 1,
+            pos.1 + board.height() as i64 - 1,
+        );
+        for tile_y in from.1..=to.1 {
+            for tile_x in from.0..=to.0 {
+                let tile = match self.tiles.get(&(tile_x, tile_y)) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                for (y, &row) in (0..).zip(tile.iter()) {
+                    for x in (0..TILE_SIZE).filter(|x| row & (1 << x) != 0) {
+                        let x = tile_x * TILE_SIZE + x - pos.0;
+                        let y = tile_y * TILE_SIZE + y - pos.1;
+                        board.set(x, y, true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Tiles are stepped one at a time as dense boards with one cell wide margin
+// taken from the neighbouring tiles
+const PADDED_SIZE: usize = TILE_SIZE as usize + 2;
+
+impl SparseBoard {
+    fn tile_row(&self, tile: (i64, i64), row: i64) -> u64 {
+        let tile = (tile.0, tile.1 + row.div_euclid(TILE_SIZE));
+        self.tiles
+            .get(&tile)
+            .map_or(0, |tile| tile[row.rem_euclid(TILE_SIZE) as usize])
+    }
+
+    fn padded_tile(&self, (tile_x, tile_y): (i64, i64)) -> Board {
+        let mut board = Board::create(PADDED_SIZE, PADDED_SIZE);
+        for (y, words) in board.words.chunks_exact_mut(2).enumerate() {
+            let row = y as i64 - 1;
+            let west = self.tile_row((tile_x - 1, tile_y), row);
+            let centre = self.tile_row((tile_x, tile_y), row);
+            let east = self.tile_row((tile_x + 1, tile_y), row);
+            words[0] = (west >> 63) | (centre << 1);
+            words[1] = (centre >> 63) | ((east & 1) << 1);
+        }
+        board
+    }
+
+    // Steps the board by applying `next_board` on every tile which may contain
+    // live cells in the next generation
+    pub fn step_tiles<F>(&self, mut next_board: F) -> SparseBoard
+    where
+        F: FnMut(&Board) -> Board,
+    {
+        let candidates: HashSet<_> = self
+            .tiles
+            .keys()
+            .flat_map(|&(x, y)| (-1..=1).flat_map(move |i| (-1..=1).map(move |j| (x + i, y + j))))
+            .collect();
+
+        let mut next = SparseBoard::create();
+        for tile_pos in candidates {
+            let padded = next_board(&self.padded_tile(tile_pos));
+            assert_eq!(padded.words_per_row(), 2);
+            let mut tile = [0; TILE_SIZE as usize];
+            for (row, words) in tile.iter_mut().zip(padded.words.chunks_exact(2).skip(1)) {
+                *row = (words[0] >> 1) | (words[1] << 63);
+            }
+            if !is_empty(&tile) {
+                next.tiles.insert(tile_pos, tile);
+            }
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_of_life::rules::{LifeLike, Rule};
+
+    const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    fn sorted_cells(board: &SparseBoard) -> Vec<(i64, i64)> {
+        let mut cells: Vec<_> = board.indexed_iter().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn set_and_get_across_tiles() {
+        let mut board = SparseBoard::create();
+        let cells = [(-1, -1), (0, 0), (63, 63), (64, 0), (-64, 5), (-65, -129)];
+        for &(x, y) in &cells {
+            board.set(x, y, true);
+        }
+        for &(x, y) in &cells {
+            assert!(board.get(x, y), "{} {}", x, y);
+        }
+        assert!(!board.get(-1, 0));
+        assert!(!board.get(63, 0));
+        assert_eq!(board.population(), cells.len());
+        assert_eq!(board.tile_count(), 5);
+        let mut expected = cells.to_vec();
+        expected.sort_unstable();
+        assert_eq!(sorted_cells(&board), expected);
+        assert_eq!(board.bounding_box(), Some(((-65, -129), (65, 64))));
+
+        // Tiles are freed once they are empty
+        for &(x, y) in &cells {
+            board.toggle(x, y);
+        }
+        assert_eq!(board.tile_count(), 0);
+        assert_eq!(board.bounding_box(), None);
+    }
+
+    #[test]
+    fn reads_and_writes_boards() {
+        let mut dense = Board::create(70, 3);
+        dense.set(0, 0, true);
+        dense.set(69, 2, true);
+        let mut board = SparseBoard::create();
+        board.read_board(&dense, (-66, -1));
+        assert_eq!(sorted_cells(&board), vec![(-66, -1), (3, 1)]);
+
+        let mut view = Board::create(70, 3);
+        board.write_board(&mut view, (-66, -1));
+        assert_eq!(view, dense);
+        board.write_board(&mut view, (-65, -1));
+        assert_eq!(view.population(), 1);
+        assert_eq!(view.get(68, 2), Some(&true));
+    }
+
+    #[test]
+    fn steps_across_tile_edges() {
+        // Glider flying south-east from negative coordinates through the
+        // corner of four tiles, compared to a large enough dense board
+        let start = (-10, -10);
+        let mut board = SparseBoard::create();
+        let mut dense = Board::create(48, 48);
+        for &(x, y) in &GLIDER {
+            board.set(start.0 + x, start.1 + y, true);
+            dense.set(x as i32 + 4, y as i32 + 4, true);
+        }
+        let mut rule = LifeLike::conway();
+        for generation in 1..=80 {
+            board = rule.next_sparse_board(&board).unwrap();
+            dense = rule.next_board(&dense);
+            let mut expected = SparseBoard::create();
+            expected.read_board(&dense, (start.0 - 4, start.1 - 4));
+            assert_eq!(board, expected, "generation {}", generation);
+        }
+        assert_eq!(board.bounding_box(), Some(((10, 10), (13, 13))));
+    }
+}
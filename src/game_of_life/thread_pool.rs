@@ -0,0 +1,45 @@
+// Worker threads computing generations in bands of rows, kept for the whole
+// game, so that they are not spawned again every generation
+
+#[derive(Debug)]
+pub struct ThreadPool {
+    pool: rayon::ThreadPool,
+}
+
+impl ThreadPool {
+    pub fn create(threads: usize) -> ThreadPool {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|i| format!("game-of-life-{}", i))
+            .build()
+            .expect("Failed to start worker threads");
+        ThreadPool { pool }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    // Splits `out`, which holds `row_len` items per row, into one band of
+    // whole rows per thread and calls `f` with the rows and the items of every
+    // band, returns once all of them are done
+    pub fn for_each_band<T, F>(&self, out: &mut [T], row_len: usize, f: F)
+    where
+        T: Send,
+        F: Fn(std::ops::Range<usize>, &mut [T]) + Sync,
+    {
+        if row_len == 0 || out.is_empty() {
+            return;
+        }
+        let height = out.len() / row_len;
+        let rows_per_band = height.div_ceil(self.threads().min(height));
+        let f = &f;
+        self.pool.scope(|scope| {
+            for (i, band) in out.chunks_mut(rows_per_band * row_len).enumerate() {
+                let from = i * rows_per_band;
+                let rows = from..from + band.len() / row_len;
+                scope.spawn(move |_| f(rows, band));
+            }
+        });
+    }
+}
@@ -0,0 +1,116 @@
+// Describes how the edges of the board are glued together
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    // Cells outside of the board are dead
+    #[default]
+    Plane,
+    // Left-right and top-bottom edges are joined
+    Torus,
+    // Left-right edges are joined
+    HorizontalCylinder,
+    // Top-bottom edges are joined
+    VerticalCylinder,
+    // Left-right edges are joined, top-bottom edges are joined with a flip
+    KleinBottle,
+    // Both pairs of edges are joined with a flip (real projective plane)
+    CrossSurface,
+}
+
+impl Topology {
+    pub fn all() -> Vec<Topology> {
+        vec![
+            Self::Plane,
+            Self::Torus,
+            Self::HorizontalCylinder,
+            Self::VerticalCylinder,
+            Self::KleinBottle,
+            Self::CrossSurface,
+        ]
+    }
+
+    fn wraps(&self) -> (bool, bool) {
+        match self {
+            Self::Plane => (false, false),
+            Self::HorizontalCylinder => (true, false),
+            Self::VerticalCylinder => (false, true),
+            Self::Torus | Self::KleinBottle | Self::CrossSurface => (true, true),
+        }
+    }
+
+    fn flips(&self) -> (bool, bool) {
+        match self {
+            Self::KleinBottle => (false, true),
+            Self::CrossSurface => (true, true),
+            _ => (false, false),
+        }
+    }
+
+    // Maps position (possibly outside of the board) to the cell it refers to,
+    // None when the position lies outside of the surface
+    pub fn map(&self, (width, height): (i32, i32), (x, y): (i32, i32)) -> Option<(i32, i32)> {
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        let (wrap_x, wrap_y) = self.wraps();
+        let (flip_x, flip_y) = self.flips();
+        let turns_x = x.div_euclid(width);
+        let turns_y = y.div_euclid(height);
+        if (turns_x != 0 && !wrap_x) || (turns_y != 0 && !wrap_y) {
+            return None;
+        }
+
+        let mut x = x.rem_euclid(width);
+        let mut y = y.rem_euclid(height);
+        // Crossing left-right edge flips vertically and vice versa
+        if flip_x && turns_x % 2 != 0 {
+            y = height - 1 - y;
+        }
+        if flip_y && turns_y % 2 != 0 {
+            x = width - 1 - x;
+        }
+        Some((x, y))
+    }
+}
+
+impl std::fmt::Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Plane => "plane",
+            Self::Torus => "torus",
+            Self::HorizontalCylinder => "horizontal-cylinder",
+            Self::VerticalCylinder => "vertical-cylinder",
+            Self::KleinBottle => "klein-bottle",
+            Self::CrossSurface => "cross-surface",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTopologyError(pub String);
+
+impl std::fmt::Display for ParseTopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = Topology::all().iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "Unknown topology '{}' (expected one of: {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseTopologyError {}
+
+impl std::str::FromStr for Topology {
+    type Err = ParseTopologyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        Topology::all()
+            .into_iter()
+            .find(|topology| topology.to_string() == name)
+            .ok_or_else(|| ParseTopologyError(s.into()))
+    }
+}
@@ -1,49 +1,430 @@
 pub mod config;
 pub use config::Config;
 
+pub mod palette;
+pub use palette::Palette;
+
+pub mod resize_policy;
+pub use resize_policy::ResizePolicy;
+
+pub mod speed;
+pub use speed::Speed;
+
 use crate::game_of_life;
 use crate::window;
 
 use sdl2::pixels::Color;
-use std::convert::TryFrom;
+use sdl2::rect::Rect;
+
+// How long messages of `Config::show_message` stay in the HUD
+const MESSAGE_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+const BORDER_COLOR: Color = Color::RGB(64, 64, 64);
+// Hexagonal cells smaller than this (in pixels) are drawn as rectangles
+const MIN_HEXAGON_HEIGHT: f64 = 6.0;
 
+// Rectangle on the screen covering [from, to) of the view, see `Camera`
+fn view_to_rect(window_data: &window::WindowData, from: (f64, f64), to: (f64, f64)) -> Rect {
+    let size = &window_data.window_config.size;
+    let from = window_data.camera.view_to_window(size, from);
+    let to = window_data.camera.view_to_window(size, to);
+    let (x, y) = (from.0.floor(), from.1.floor());
+    let width = (to.0.floor() - x).max(1.0);
+    let height = (to.1.floor() - y).max(1.0);
+    Rect::new(x as i32, y as i32, width as u32, height as u32)
+}
+
+// Draws the part of the board visible through the camera in the colors of
+// the rule, by default live cells are white and dying cells fade out
 pub fn display_game(
     window_data: &mut window::WindowData,
     game_board: &game_of_life::Board,
+    rule: &dyn game_of_life::rules::Rule,
+) -> window::Result<()> {
+    let background = window_data.window_config.background_color;
+    display_cells(window_data, game_board, |state, _| match state {
+        0 => None,
+        _ => Some(state_color(rule, state, Palette::Plain, background)),
+    })
+}
+
+// Color of a live or dying cell given by the rule, if any
+fn state_color(
+    rule: &dyn game_of_life::rules::Rule,
+    state: u8,
+    palette: Palette,
+    background: Color,
+) -> Color {
+    match rule.state_color(state) {
+        Some((r, g, b)) => Color::RGB(r, g, b),
+        None if state == 1 => Color::WHITE,
+        None => palette.dying_color(state, rule.states(), background),
+    }
+}
+
+// Draws the board colored by the age of cells, falls back to `display_game`
+// when ages are not tracked
+pub fn display_game_with_ages(
+    window_data: &mut window::WindowData,
+    game_board: &game_of_life::Board,
+    rule: &dyn game_of_life::rules::Rule,
+    ages: Option<&game_of_life::CellAges>,
+    palette: Palette,
+    trail: bool,
 ) -> window::Result<()> {
-    let cell_width = window_data.window_config.size.cell_width;
-    let cell_height = window_data.window_config.size.cell_height;
-    assert!(i32::try_from(cell_width).is_ok());
-    assert!(i32::try_from(cell_height).is_ok());
-
-    window_data.canvas.set_draw_color(Color::WHITE);
-    for (row, x) in game_board.into_iter().zip(0..) {
-        for (&cell, y) in row.iter().zip(0..) {
-            if cell {
-                let x_point = x * cell_width as i32;
-                let y_point = y * cell_height as i32;
-                let rect = sdl2::rect::Rect::new(x_point, y_point, cell_width, cell_height);
-                window_data.canvas.fill_rect(rect)?;
+    let ages = match ages {
+        Some(ages) => ages,
+        None => return display_game(window_data, game_board, rule),
+    };
+    let background = window_data.window_config.background_color;
+    display_cells(window_data, game_board, |state, (x, y)| {
+        let age = ages.get(x, y)?;
+        match state {
+            0 if trail => palette.trail_color(age, background),
+            0 => None,
+            1 => Some(palette.alive_color(age)),
+            _ => Some(state_color(rule, state, palette, background)),
+        }
+    })
+}
+
+// Draws the visible cells for which `color` returns a color given the state
+// of the cell, see `Board::get_state`
+fn display_cells<F>(
+    window_data: &mut window::WindowData,
+    game_board: &game_of_life::Board,
+    mut color: F,
+) -> window::Result<()>
+where
+    F: FnMut(u8, (i32, i32)) -> Option<Color>,
+{
+    let size = &window_data.window_config.size;
+    let camera = &window_data.camera;
+    let (width, height) = (game_board.width() as i32, game_board.height() as i32);
+    let (window_width, window_height) = window_data.output_size();
+    let (window_width, window_height) = (window_width as i32, window_height as i32);
+    // Rows are shifted on hexagonal grids, so all corners are needed
+    let corners = [
+        (0, 0),
+        (window_width, 0),
+        (0, window_height),
+        (window_width, window_height),
+    ]
+    .iter()
+    .map(|&corner| camera.window_to_cell(size, corner))
+    .collect::<Vec<_>>();
+    let min = |coordinate: fn(&(f64, f64)) -> f64| {
+        corners.iter().map(coordinate).fold(f64::INFINITY, f64::min)
+    };
+    let max = |coordinate: fn(&(f64, f64)) -> f64| {
+        corners
+            .iter()
+            .map(coordinate)
+            .fold(f64::NEG_INFINITY, f64::max)
+    };
+    let (from_x, from_y) = (
+        (min(|corner| corner.0).floor() as i32 - 1).max(0),
+        (min(|corner| corner.1).floor() as i32 - 1).max(0),
+    );
+    let (to_x, to_y) = (
+        (max(|corner| corner.0).ceil() as i32 + 1).min(width),
+        (max(|corner| corner.1).ceil() as i32 + 1).min(height),
+    );
+
+    display_border(window_data, game_board)?;
+
+    for y in from_y..to_y {
+        for x in from_x..to_x {
+            let state = game_board.get_state(x, y).unwrap_or(0);
+            if let Some(color) = color(state, (x, y)) {
+                window_data.canvas.set_draw_color(color);
+                fill_cell(window_data, (x, y))?;
             }
         }
     }
     Ok(())
 }
 
-pub fn get_cell_from_window_pos(
-    window_size: &window::WindowSize,
-    (x, y): (i32, i32),
-) -> (i32, i32) {
-    let get_div = |lhs: i32, rhs: u32| -> i32 {
-        lhs / i32::try_from(rhs).expect("cell size cannot be represented by i32")
+// Outline of the board, a parallelogram on hexagonal grids
+fn display_border(
+    window_data: &mut window::WindowData,
+    game_board: &game_of_life::Board,
+) -> window::Result<()> {
+    let (width, height) = (game_board.width() as f64, game_board.height() as f64);
+    window_data.canvas.set_draw_color(BORDER_COLOR);
+    if !window_data.camera.hexagonal {
+        let border = view_to_rect(window_data, (0.0, 0.0), (width, height));
+        return Ok(window_data.canvas.draw_rect(border)?);
+    }
+    let size = &window_data.window_config.size;
+    let camera = &window_data.camera;
+    // Left edges of the first and the last row
+    let shift = -(height - 1.0) / 2.0;
+    let corners = [
+        (0.0, 0.0),
+        (width, 0.0),
+        (width + shift, height),
+        (shift, height),
+    ];
+    let points = corners
+        .iter()
+        .chain(corners.first())
+        .map(|&corner| {
+            let (x, y) = camera.view_to_window(size, corner);
+            sdl2::rect::Point::new(x.floor() as i32, y.floor() as i32)
+        })
+        .collect::<Vec<_>>();
+    Ok(window_data.canvas.draw_lines(&points[..])?)
+}
+
+// Fills the cell with the current draw color, as a hexagon on hexagonal grids
+fn fill_cell(window_data: &mut window::WindowData, (x, y): (i32, i32)) -> window::Result<()> {
+    let size = &window_data.window_config.size;
+    let camera = &window_data.camera;
+    let centre = camera.cell_to_view((f64::from(x) + 0.5, f64::from(y) + 0.5));
+    let (cell_width, cell_height) = camera.cell_size(size);
+    if !camera.hexagonal || cell_height < MIN_HEXAGON_HEIGHT {
+        let from = (centre.0 - 0.5, centre.1 - 0.5);
+        let to = (centre.0 + 0.5, centre.1 + 0.5);
+        let rect = view_to_rect(window_data, from, to);
+        return Ok(window_data.canvas.fill_rect(rect)?);
+    }
+    // The hexagon is one cell wide and 4/3 cells high, so that the pointed
+    // tops fit between the cells of the row above
+    let centre = camera.view_to_window(size, centre);
+    let top = (centre.1 - cell_height * 2.0 / 3.0).floor() as i32;
+    let bottom = (centre.1 + cell_height * 2.0 / 3.0).ceil() as i32;
+    let rows = (top..bottom)
+        .filter_map(|row| {
+            let distance = (f64::from(row) + 0.5 - centre.1).abs() / cell_height;
+            let half_width = if distance <= 1.0 / 3.0 {
+                0.5
+            } else {
+                1.5 * (2.0 / 3.0 - distance)
+            };
+            if half_width <= 0.0 {
+                return None;
+            }
+            let from = (centre.0 - half_width * cell_width).round();
+            let to = (centre.0 + half_width * cell_width).round();
+            Some(Rect::new(from as i32, row, (to - from).max(1.0) as u32, 1))
+        })
+        .collect::<Vec<_>>();
+    Ok(window_data.canvas.fill_rects(&rows[..])?)
+}
+
+pub fn get_cell_from_window_pos(window_data: &window::WindowData, pos: (i32, i32)) -> (i32, i32) {
+    let size = &window_data.window_config.size;
+    window_data.camera.cell_at(size, pos)
+}
+
+const HUD_SCALE: u32 = 2;
+const HUD_MARGIN: i32 = 8;
+
+// Text overlay with the state of the game in the top left corner
+pub fn display_hud(
+    window_data: &mut window::WindowData,
+    game: &game_of_life::Game,
+    game_config: &Config,
+) -> window::Result<()> {
+    let state = match (game_config.running, game_config.target_generation) {
+        (true, Some(target)) => format!("running to {}", target),
+        (true, None) => "running".into(),
+        (false, _) => "paused".into(),
     };
+    let mut lines = Vec::new();
+    if game_config.show_hud {
+        lines.push(format!(
+            "generation {}\npopulation {}\nrule {}\ntopology {}\n{}\nspeed {}\nfps {:.0}",
+            game.generation,
+            game.board.population(),
+            game.rule.notation().unwrap_or_else(|| "custom".into()),
+            game.board.topology(),
+            state,
+            game_config.speed,
+            window_data.fps,
+        ));
+        let states = game.rule.states();
+        if states > 2 {
+            lines.push(format!("drawing state {}", draw_state(game_config, states)));
+        }
+    }
+    // Messages are shown even with the rest of the HUD hidden
+    match &game_config.message {
+        Some((message, shown)) if shown.elapsed() < MESSAGE_DURATION => lines.push(message.clone()),
+        _ => {}
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let text = lines.join("\n");
 
-    (
-        get_div(x, window_size.cell_width),
-        get_div(y, window_size.cell_height),
+    let (width, height) = window::text::text_size(&text, HUD_SCALE);
+    let padding = HUD_SCALE * 2;
+    let background = Rect::new(
+        HUD_MARGIN,
+        HUD_MARGIN,
+        width + 2 * padding,
+        height + 2 * padding,
+    );
+    window_data
+        .canvas
+        .set_blend_mode(sdl2::render::BlendMode::Blend);
+    window_data.canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    window_data.canvas.fill_rect(background)?;
+    window_data
+        .canvas
+        .set_blend_mode(sdl2::render::BlendMode::None);
+
+    let pos = (HUD_MARGIN + padding as i32, HUD_MARGIN + padding as i32);
+    window::text::draw_text(
+        &mut window_data.canvas,
+        pos,
+        &text,
+        HUD_SCALE,
+        Color::YELLOW,
     )
 }
 
+// Zooms to the live cells, or the whole board when it is empty
+pub fn fit_to_pattern(window_data: &mut window::WindowData, game_board: &game_of_life::Board) {
+    let shape = game_of_life::Shape::from_board(game_board);
+    let ((x, y), (width, height)) = if game_board.population() > 0 {
+        (shape.pos(), shape.dimensions())
+    } else {
+        (
+            (0, 0),
+            (game_board.width() as i32, game_board.height() as i32),
+        )
+    };
+    // One cell wide margin
+    let from = (f64::from(x) - 1.0, f64::from(y) - 1.0);
+    let to = (f64::from(x + width) + 1.0, f64::from(y + height) + 1.0);
+    let size = &window_data.window_config.size;
+    let window = window_data.output_size();
+    window_data.camera.fit(size, window, from, to);
+}
+
+pub fn save_snapshot(
+    game: &game_of_life::Game,
+    game_config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = game_of_life::snapshot::write(game, &game_config.to_fields())?;
+    std::fs::write(&game_config.snapshot_path, snapshot)?;
+    Ok(())
+}
+
+// Loads game from the snapshot and updates config with the values saved with it,
+// the thread count is not part of the snapshot
+pub fn load_snapshot(
+    path: &std::path::Path,
+    game_config: &mut Config,
+    threads: usize,
+) -> Result<game_of_life::Game<'static>, Box<dyn std::error::Error>> {
+    let (game, fields) = game_of_life::snapshot::read(&std::fs::read_to_string(path)?)?;
+    game_config.update_from_fields(&fields)?;
+    Ok(game.with_threads(threads))
+}
+
+pub fn window_title(game_config: &Config) -> String {
+    format!("Game of life (seed {})", game_config.seed)
+}
+
+// Fills the board with random cells, the seed is shown in the window title
+// and printed so that the board can be regenerated with `--seed`
+fn randomize(
+    window_data: &mut window::WindowData,
+    game: &mut game_of_life::Game,
+    game_config: &Config,
+) {
+    game.edit(|board| board.randomize_seeded(game_config.seed, game_config.density));
+    println!("Seed: {}", game_config.seed);
+    let _ = window_data
+        .canvas
+        .window_mut()
+        .set_title(&window_title(game_config));
+}
+
+// Places soup centred at `pos`, the seed is shown like with `randomize`
+fn add_soup(
+    window_data: &mut window::WindowData,
+    game: &mut game_of_life::Game,
+    game_config: &mut Config,
+    pos: (i32, i32),
+) {
+    let (width, height) = game_config.symmetry.dimensions(game_config.soup_size);
+    let pos = (pos.0 - width as i32 / 2, pos.1 - height as i32 / 2);
+    let (seed, density) = (game_config.seed, game_config.density);
+    let symmetry = game_config.symmetry;
+    let size = game_config.soup_size;
+    if game
+        .edit(|board| board.add_soup(seed, density, symmetry, pos, size))
+        .is_none()
+    {
+        game_config.show_message("soup does not fit the board");
+        return;
+    }
+    println!("Seed: {}, symmetry: {}", seed, symmetry);
+    let _ = window_data
+        .canvas
+        .window_mut()
+        .set_title(&window_title(game_config));
+}
+
+// Adapts the board or the view to the new size of the window
+pub fn handle_resize(
+    window_data: &mut window::WindowData,
+    game: &mut game_of_life::Game,
+    policy: ResizePolicy,
+) {
+    let window = window_data.output_size();
+    let size = &window_data.window_config.size;
+    let camera = &mut window_data.camera;
+    let board_size =
+        |game: &game_of_life::Game| (game.board.width() as f64, game.board.height() as f64);
+    match policy {
+        ResizePolicy::RescaleCells => camera.fit(size, window, (0.0, 0.0), board_size(game)),
+        ResizePolicy::ResizeBoard => {
+            let (cell_width, cell_height) = camera.cell_size(size);
+            let width = (f64::from(window.0) / cell_width).floor().max(1.0);
+            let height = (f64::from(window.1) / cell_height).floor().max(1.0);
+            game.resize(width as usize, height as usize);
+            camera.center(size, window, (0.0, 0.0), board_size(game));
+        }
+        ResizePolicy::Letterbox => camera.center(size, window, (0.0, 0.0), board_size(game)),
+    }
+}
+
+// Drawing state limited to the live and dying states of the rule
+fn draw_state(game_config: &Config, states: u8) -> u8 {
+    game_config.draw_state.clamp(1, states.max(2) - 1)
+}
+
+// Going back in time pauses the simulation, otherwise the next step would
+// drop the undone states
+fn undo(game: &mut game_of_life::Game, game_config: &std::cell::RefCell<Config>) {
+    if game.undo().is_some() {
+        game_config.borrow_mut().running = false;
+    }
+}
+
+// Runs the simulation until the generation is reached, earlier generations
+// are looked up in the history
+fn run_to_generation(game: &mut game_of_life::Game, game_config: &mut Config, generation: u64) {
+    while game.generation > generation && game.undo().is_some() {}
+    if game.generation > generation {
+        game_config.show_message(format!(
+            "Generation {} is no longer in the history",
+            generation
+        ));
+        game_config.running = false;
+    } else if game.generation < generation {
+        game_config.target_generation = Some(generation);
+        game_config.running = true;
+    } else {
+        game_config.running = false;
+    }
+}
+
 pub fn add_input_cb_to_handler<'a>(
     callback_handler: &mut window::CallbackHandler<'a, window::WindowData>,
     game: &'a std::cell::RefCell<game_of_life::Game>,
@@ -55,58 +436,256 @@ pub fn add_input_cb_to_handler<'a>(
         let mut oscilator_shapes = shapes::oscilators::all().into_iter().cycle();
         let mut ship_shapes = shapes::ships::all().into_iter().cycle();
         let mut curious_shapes = shapes::curious::all().into_iter().cycle();
+        // Digits typed before G
+        let mut generation_input = String::new();
 
         move |window_data, event| {
             use sdl2::keyboard::Keycode;
             if let sdl2::event::Event::KeyDown {
                 keycode: Some(keycode),
-                repeat: false,
+                keymod,
+                repeat,
                 ..
-            } = event
+            } = *event
             {
-                let mouse_pos = || {
-                    get_cell_from_window_pos(&window_data.window_config.size, window_data.mouse_pos)
-                };
+                use sdl2::keyboard::Mod;
+                let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+                // Only scrubbing through the history and panning repeat while
+                // the key is held
+                let repeats = matches!(
+                    keycode,
+                    Keycode::Comma
+                        | Keycode::Period
+                        | Keycode::Left
+                        | Keycode::Right
+                        | Keycode::Up
+                        | Keycode::Down
+                );
+                if repeat && !repeats {
+                    return Ok(());
+                }
+                let mouse_pos = || get_cell_from_window_pos(window_data, window_data.mouse_pos);
+                // Arrows pan by a tenth of the window
+                let pan_step = (
+                    window_data.output_size().0 as i32 / 10,
+                    window_data.output_size().1 as i32 / 10,
+                );
                 match keycode {
+                    Keycode::Left | Keycode::Right | Keycode::Up | Keycode::Down => {
+                        let direction = match keycode {
+                            Keycode::Left => (-pan_step.0, 0),
+                            Keycode::Right => (pan_step.0, 0),
+                            Keycode::Up => (0, -pan_step.1),
+                            _ => (0, pan_step.1),
+                        };
+                        let size = &window_data.window_config.size;
+                        window_data.camera.pan(size, direction);
+                    }
+                    Keycode::A => {
+                        let mut game_config = game_config.borrow_mut();
+                        let palettes = Palette::all();
+                        let index = palettes
+                            .iter()
+                            .position(|&palette| palette == game_config.palette)
+                            .map_or(0, |index| (index + 1) % palettes.len());
+                        game_config.palette = palettes[index];
+                        game_config.update_ages(&mut game.borrow_mut());
+                        let message = format!("palette {}", game_config.palette);
+                        game_config.show_message(message);
+                    }
+                    Keycode::T => {
+                        let mut game_config = game_config.borrow_mut();
+                        game_config.trail = !game_config.trail;
+                        game_config.update_ages(&mut game.borrow_mut());
+                    }
+                    Keycode::H => {
+                        let mut game_config = game_config.borrow_mut();
+                        game_config.show_hud = !game_config.show_hud;
+                    }
+                    Keycode::M => {
+                        let mut game_config = game_config.borrow_mut();
+                        let policies = ResizePolicy::all();
+                        let index = policies
+                            .iter()
+                            .position(|&policy| policy == game_config.resize_policy)
+                            .map_or(0, |index| (index + 1) % policies.len());
+                        game_config.resize_policy = policies[index];
+                        let message = format!("resize policy {}", game_config.resize_policy);
+                        game_config.show_message(message);
+                        handle_resize(
+                            window_data,
+                            &mut game.borrow_mut(),
+                            game_config.resize_policy,
+                        );
+                    }
+                    Keycode::F if shift => window_data.camera = window::Camera::default(),
+                    Keycode::F => fit_to_pattern(window_data, &game.borrow().board),
+                    Keycode::Z if ctrl && !shift => undo(&mut game.borrow_mut(), game_config),
+                    Keycode::Y | Keycode::Z if ctrl => {
+                        let _ = game.borrow_mut().redo();
+                    }
+                    Keycode::Comma => undo(&mut game.borrow_mut(), game_config),
+                    Keycode::Period => {
+                        let _ = game.borrow_mut().redo();
+                    }
+                    Keycode::Home => {
+                        let mut game = game.borrow_mut();
+                        while game.undo().is_some() {}
+                        game_config.borrow_mut().running = false;
+                    }
+                    Keycode::End => {
+                        let mut game = game.borrow_mut();
+                        while game.redo().is_some() {}
+                    }
                     Keycode::Space | Keycode::P => {
                         let mut game_config = game_config.borrow_mut();
                         game_config.running = !game_config.running;
+                        game_config.target_generation = None;
+                    }
+                    Keycode::N => {
+                        let mut game_config = game_config.borrow_mut();
+                        game_config.running = false;
+                        game_config.target_generation = None;
+                        game.borrow_mut().step();
+                    }
+                    Keycode::Plus | Keycode::KpPlus | Keycode::Equals => {
+                        let mut game_config = game_config.borrow_mut();
+                        game_config.speed = game_config.speed.faster();
+                        let message = format!("speed {}", game_config.speed);
+                        game_config.show_message(message);
+                    }
+                    Keycode::Minus | Keycode::KpMinus => {
+                        let mut game_config = game_config.borrow_mut();
+                        game_config.speed = game_config.speed.slower();
+                        let message = format!("speed {}", game_config.speed);
+                        game_config.show_message(message);
+                    }
+                    Keycode::Num0
+                    | Keycode::Num1
+                    | Keycode::Num2
+                    | Keycode::Num3
+                    | Keycode::Num4
+                    | Keycode::Num5
+                    | Keycode::Num6
+                    | Keycode::Num7
+                    | Keycode::Num8
+                    | Keycode::Num9 => {
+                        let digit = keycode as i32 - Keycode::Num0 as i32;
+                        generation_input.push_str(&digit.to_string());
+                        let message = format!("run to generation {}", generation_input);
+                        game_config.borrow_mut().show_message(message);
+                    }
+                    Keycode::Backspace => {
+                        generation_input.pop();
+                        let message = format!("run to generation {}", generation_input);
+                        game_config.borrow_mut().show_message(message);
+                    }
+                    Keycode::G => match generation_input.parse() {
+                        Ok(generation) => {
+                            generation_input.clear();
+                            let mut game_config = game_config.borrow_mut();
+                            run_to_generation(&mut game.borrow_mut(), &mut game_config, generation);
+                        }
+                        Err(_) => game_config
+                            .borrow_mut()
+                            .show_message("type generation number before pressing G"),
+                    },
+                    Keycode::F5 => {
+                        let mut game_config = game_config.borrow_mut();
+                        let message = match save_snapshot(&game.borrow(), &game_config) {
+                            Ok(()) => format!("saved to {}", game_config.snapshot_path.display()),
+                            Err(e) => format!("failed to save game: {}", e),
+                        };
+                        game_config.show_message(message);
+                    }
+                    Keycode::F9 => {
+                        let mut game_config = game_config.borrow_mut();
+                        let path = game_config.snapshot_path.clone();
+                        let threads = game.borrow().threads();
+                        let message = match load_snapshot(&path, &mut game_config, threads) {
+                            Ok(mut loaded) => {
+                                loaded.set_history(game_config.history());
+                                game_config.update_ages(&mut loaded);
+                                *game.borrow_mut() = loaded;
+                                format!("loaded from {}", path.display())
+                            }
+                            Err(e) => format!("failed to load game: {}", e),
+                        };
+                        game_config.show_message(message);
                     }
                     Keycode::X => {
-                        let mut game = game.borrow_mut();
-                        game.randomize();
+                        let mut game_config = game_config.borrow_mut();
+                        // With shift the last random board is regenerated
+                        if !shift {
+                            game_config.seed = rand::random();
+                        }
+                        randomize(window_data, &mut game.borrow_mut(), &game_config);
+                    }
+                    Keycode::S => {
+                        let pos = mouse_pos();
+                        let mut game_config = game_config.borrow_mut();
+                        if !shift {
+                            game_config.seed = rand::random();
+                        }
+                        add_soup(window_data, &mut game.borrow_mut(), &mut game_config, pos);
+                    }
+                    Keycode::D => {
+                        let mut game_config = game_config.borrow_mut();
+                        let symmetries = game_of_life::Symmetry::all();
+                        let index = symmetries
+                            .iter()
+                            .position(|&symmetry| symmetry == game_config.symmetry)
+                            .map_or(0, |index| (index + 1) % symmetries.len());
+                        game_config.symmetry = symmetries[index];
+                        let message = format!("soup symmetry {}", game_config.symmetry);
+                        game_config.show_message(message);
+                    }
+                    Keycode::LeftBracket | Keycode::RightBracket => {
+                        let mut game_config = game_config.borrow_mut();
+                        let states = game.borrow().rule.states();
+                        let state = draw_state(&game_config, states);
+                        let last = states.max(2) - 1;
+                        game_config.draw_state = match keycode {
+                            Keycode::RightBracket if state == last => 1,
+                            Keycode::RightBracket => state + 1,
+                            _ if state == 1 => last,
+                            _ => state - 1,
+                        };
+                        let message = format!("drawing state {}", game_config.draw_state);
+                        game_config.show_message(message);
                     }
                     Keycode::C => {
                         let mut game = game.borrow_mut();
-                        game.clear();
+                        game.edit(game_of_life::Board::clear);
                     }
                     Keycode::Q => {
                         let pos = mouse_pos();
                         let mut game = game.borrow_mut();
                         let _ = still_shapes
                             .next()
-                            .and_then(|shape| shape.add(&mut game.board, pos));
+                            .and_then(|shape| game.edit(|board| shape.add(board, pos)));
                     }
                     Keycode::W => {
                         let pos = mouse_pos();
                         let mut game = game.borrow_mut();
                         let _ = oscilator_shapes
                             .next()
-                            .and_then(|shape| shape.add(&mut game.board, pos));
+                            .and_then(|shape| game.edit(|board| shape.add(board, pos)));
                     }
                     Keycode::E => {
                         let pos = mouse_pos();
                         let mut game = game.borrow_mut();
                         let _ = ship_shapes
                             .next()
-                            .and_then(|shape| shape.add(&mut game.board, pos));
+                            .and_then(|shape| game.edit(|board| shape.add(board, pos)));
                     }
                     Keycode::R => {
                         let pos = mouse_pos();
                         let mut game = game.borrow_mut();
                         let _ = curious_shapes
                             .next()
-                            .and_then(|shape| shape.add(&mut game.board, pos));
+                            .and_then(|shape| game.edit(|board| shape.add(board, pos)));
                     }
                     _ => {}
                 }
@@ -120,9 +699,41 @@ pub fn add_input_cb_to_handler<'a>(
             } = *event
             {
                 let mut game = game.borrow_mut();
-                let (x_cell, y_cell) =
-                    get_cell_from_window_pos(&window_data.window_config.size, (x, y));
-                let _ = game.board.toggle(x_cell, y_cell);
+                let (x_cell, y_cell) = get_cell_from_window_pos(window_data, (x, y));
+                let state = draw_state(&game_config.borrow(), game.rule.states());
+                // Cells already in the drawing state are cleared
+                let _ = game.edit(|board| {
+                    let old_state = board.get_state(x_cell, y_cell)?;
+                    let new_state = if old_state == state { 0 } else { state };
+                    board.set_state(x_cell, y_cell, new_state)
+                });
+            }
+            match *event {
+                sdl2::event::Event::Window {
+                    win_event: sdl2::event::WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    let policy = game_config.borrow().resize_policy;
+                    handle_resize(window_data, &mut game.borrow_mut(), policy);
+                }
+                // Zooms in/out around the cursor
+                sdl2::event::Event::MouseWheel { y, .. } if y != 0 => {
+                    let factor = 1.25_f64.powi(y);
+                    let size = &window_data.window_config.size;
+                    let mouse_pos = window_data.mouse_pos;
+                    window_data.camera.zoom_at(size, mouse_pos, factor);
+                }
+                // Dragging with the right or middle button pans the view
+                sdl2::event::Event::MouseMotion {
+                    mousestate,
+                    xrel,
+                    yrel,
+                    ..
+                } if mousestate.right() || mousestate.middle() => {
+                    let size = &window_data.window_config.size;
+                    window_data.camera.pan(size, (-xrel, -yrel));
+                }
+                _ => {}
             }
             Ok(())
         }
@@ -137,16 +748,35 @@ pub fn add_step_update_cb_to_handler<'a>(
     callback_handler.add_frame_cb_data({
         let mut iteration = 0;
         move |_| {
-            let game_config = game_config.borrow();
+            let mut game_config = game_config.borrow_mut();
             if !game_config.running {
                 return Ok(());
             }
-            iteration += 1;
-            if iteration >= game_config.ticks_to_update {
-                iteration = 0;
+            let generations = match game_config.speed {
+                Speed::FramesPerGeneration(frames) => {
+                    iteration += 1;
+                    if iteration < frames {
+                        return Ok(());
+                    }
+                    iteration = 0;
+                    1
+                }
+                Speed::GenerationsPerFrame(generations) => generations,
+            };
 
-                let mut game = game.borrow_mut();
-                game.step();
+            let mut game = game.borrow_mut();
+            let generations = match game_config.target_generation {
+                Some(target) => u64::from(generations).min(target.saturating_sub(game.generation)),
+                None => u64::from(generations),
+            };
+            // Whole frame is a single state in the history
+            if generations > 0 {
+                game.step_batch(generations);
+            }
+            if game_config.target_generation == Some(game.generation) {
+                game_config.show_message(format!("reached generation {}", game.generation));
+                game_config.target_generation = None;
+                game_config.running = false;
             }
             Ok(())
         }
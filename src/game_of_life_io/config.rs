@@ -1,4 +1,96 @@
+use std::collections::HashMap;
+
+use super::{Palette, ResizePolicy, Speed};
+use crate::game_of_life;
+
 pub struct Config {
     pub running: bool,
-    pub ticks_to_update: u8,
+    pub speed: Speed,
+    pub resize_policy: ResizePolicy,
+    pub show_hud: bool,
+    pub palette: Palette,
+    // Dead cells fade out instead of disappearing
+    pub trail: bool,
+    // Simulation pauses once it reaches this generation
+    pub target_generation: Option<u64>,
+    pub snapshot_path: std::path::PathBuf,
+    // Seed and density of the last random board, so it can be regenerated
+    pub seed: u64,
+    pub density: f64,
+    // Soups placed by the S key
+    pub symmetry: game_of_life::Symmetry,
+    pub soup_size: usize,
+    // Limits of the undo history, no history is kept with capacity 0
+    pub history_capacity: usize,
+    pub history_memory_limit: Option<usize>,
+    // State placed by clicking on a cell, for rules with more than two states
+    pub draw_state: u8,
+    // Feedback to the last action, shown in the HUD for a while
+    pub message: Option<(String, std::time::Instant)>,
+}
+
+impl Config {
+    pub fn show_message(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), std::time::Instant::now()));
+    }
+
+    pub fn history(&self) -> Option<game_of_life::History> {
+        if self.history_capacity == 0 {
+            return None;
+        }
+        let mut history = game_of_life::History::new(self.history_capacity);
+        history.set_memory_limit(self.history_memory_limit);
+        Some(history)
+    }
+
+    // Ages of cells are tracked only when they are displayed
+    pub fn update_ages(&self, game: &mut game_of_life::Game) {
+        if self.palette.uses_ages() || self.trail {
+            game.enable_ages();
+        } else {
+            game.disable_ages();
+        }
+    }
+
+    // Fields stored in game snapshots
+    pub fn to_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("running", self.running.to_string()),
+            ("speed", self.speed.to_string()),
+            ("resize_policy", self.resize_policy.to_string()),
+            ("show_hud", self.show_hud.to_string()),
+            ("palette", self.palette.to_string()),
+            ("trail", self.trail.to_string()),
+            ("seed", self.seed.to_string()),
+            ("density", self.density.to_string()),
+            ("symmetry", self.symmetry.to_string()),
+            ("soup_size", self.soup_size.to_string()),
+        ]
+    }
+
+    pub fn update_from_fields(&mut self, fields: &HashMap<String, String>) -> Result<(), String> {
+        fn parse<T: std::str::FromStr>(
+            fields: &HashMap<String, String>,
+            field: &str,
+            value: &mut T,
+        ) -> Result<(), String> {
+            if let Some(text) = fields.get(field) {
+                *value = text
+                    .parse()
+                    .map_err(|_| format!("Invalid value '{}' of field '{}'", text, field))?;
+            }
+            Ok(())
+        }
+        parse(fields, "running", &mut self.running)?;
+        parse(fields, "speed", &mut self.speed)?;
+        parse(fields, "resize_policy", &mut self.resize_policy)?;
+        parse(fields, "show_hud", &mut self.show_hud)?;
+        parse(fields, "palette", &mut self.palette)?;
+        parse(fields, "trail", &mut self.trail)?;
+        parse(fields, "seed", &mut self.seed)?;
+        parse(fields, "density", &mut self.density)?;
+        parse(fields, "symmetry", &mut self.symmetry)?;
+        parse(fields, "soup_size", &mut self.soup_size)?;
+        Ok(())
+    }
 }
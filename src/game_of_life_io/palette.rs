@@ -0,0 +1,127 @@
+use sdl2::pixels::Color;
+
+// Generations a dead cell stays visible in trail mode
+pub const TRAIL_LENGTH: u32 = 32;
+
+// Maps age of a cell to its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    // Every live cell is white, ages are not tracked
+    #[default]
+    Plain,
+    // Newborn cells are white, turning yellow, red and dark red with age
+    Heat,
+    // Age goes through the hues of the rainbow
+    Rainbow,
+}
+
+// Age scaled to 0-1, reaching 1 after about 1000 generations
+fn age_ratio(age: u32) -> f64 {
+    (f64::from(age) + 1.0).log2().min(10.0) / 10.0
+}
+
+fn lerp(from: (u8, u8, u8), to: (u8, u8, u8), ratio: f64) -> Color {
+    let mix =
+        |from: u8, to: u8| (f64::from(from) + (f64::from(to) - f64::from(from)) * ratio) as u8;
+    Color::RGB(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+// Color of the hue in degrees with full saturation and value
+fn hue(hue: f64) -> Color {
+    let sector = (hue / 60.0).rem_euclid(6.0);
+    let rising = (sector.fract() * 255.0) as u8;
+    let falling = 255 - rising;
+    match sector as u8 {
+        0 => Color::RGB(255, rising, 0),
+        1 => Color::RGB(falling, 255, 0),
+        2 => Color::RGB(0, 255, rising),
+        3 => Color::RGB(0, falling, 255),
+        4 => Color::RGB(rising, 0, 255),
+        _ => Color::RGB(255, 0, falling),
+    }
+}
+
+impl Palette {
+    pub fn all() -> Vec<Palette> {
+        vec![Self::Plain, Self::Heat, Self::Rainbow]
+    }
+
+    pub fn uses_ages(&self) -> bool {
+        *self != Self::Plain
+    }
+
+    pub fn alive_color(&self, age: u32) -> Color {
+        let ratio = age_ratio(age);
+        match self {
+            Self::Plain => Color::WHITE,
+            Self::Heat => {
+                let stops = [(255, 255, 255), (255, 230, 0), (255, 60, 0), (120, 0, 0)];
+                let position = ratio * (stops.len() - 1) as f64;
+                let index = (position as usize).min(stops.len() - 2);
+                lerp(stops[index], stops[index + 1], position - index as f64)
+            }
+            // Red through violet
+            Self::Rainbow => hue(ratio * 300.0),
+        }
+    }
+
+    // Color of a cell in the dying `state` of a rule with `states` states,
+    // fading to the background as the cell gets closer to dead
+    pub fn dying_color(&self, state: u8, states: u8, background: Color) -> Color {
+        let fade = f64::from(state - 1) / f64::from(states.max(2));
+        match self {
+            Self::Plain => lerp(
+                (60, 110, 255),
+                (background.r, background.g, background.b),
+                fade,
+            ),
+            Self::Heat => lerp(
+                (255, 60, 0),
+                (background.r, background.g, background.b),
+                fade,
+            ),
+            Self::Rainbow => hue(fade * 300.0),
+        }
+    }
+
+    // Color of a cell dead for `dead_for` generations in trail mode, None
+    // once it faded out
+    pub fn trail_color(&self, dead_for: u32, background: Color) -> Option<Color> {
+        if dead_for >= TRAIL_LENGTH {
+            return None;
+        }
+        let trail = match self {
+            Self::Plain | Self::Rainbow => (40, 80, 160),
+            Self::Heat => (100, 30, 10),
+        };
+        let fade = f64::from(dead_for + 1) / f64::from(TRAIL_LENGTH);
+        Some(lerp(
+            trail,
+            (background.r, background.g, background.b),
+            fade,
+        ))
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Plain => "plain",
+            Self::Heat => "heat",
+            Self::Rainbow => "rainbow",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Palette::all()
+            .into_iter()
+            .find(|palette| palette.to_string() == name)
+            .ok_or_else(|| format!("Unknown palette '{}' (expected plain, heat or rainbow)", s))
+    }
+}
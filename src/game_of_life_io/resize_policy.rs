@@ -0,0 +1,45 @@
+// What happens with the board when the window is resized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizePolicy {
+    // Board stays the same, cells are scaled to fill the window
+    #[default]
+    RescaleCells,
+    // Cells keep their size, the board grows or shrinks to fill the window
+    ResizeBoard,
+    // Board and cells stay the same, the board is centred in the window
+    Letterbox,
+}
+
+impl ResizePolicy {
+    pub fn all() -> Vec<ResizePolicy> {
+        vec![Self::RescaleCells, Self::ResizeBoard, Self::Letterbox]
+    }
+}
+
+impl std::fmt::Display for ResizePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::RescaleCells => "rescale-cells",
+            Self::ResizeBoard => "resize-board",
+            Self::Letterbox => "letterbox",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for ResizePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        ResizePolicy::all()
+            .into_iter()
+            .find(|policy| policy.to_string() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown resize policy '{}' (expected rescale-cells, resize-board or letterbox)",
+                    s
+                )
+            })
+    }
+}
@@ -0,0 +1,72 @@
+// How fast the simulation runs relative to the frame rate, written as
+// `generations/frames`, e.g. `1/5` or `16/1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    // One generation every n frames
+    FramesPerGeneration(u32),
+    // n generations every frame
+    GenerationsPerFrame(u32),
+}
+
+const MAX_GENERATIONS_PER_FRAME: u32 = 1 << 16;
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self::FramesPerGeneration(5)
+    }
+}
+
+impl Speed {
+    // Doubles the speed
+    pub fn faster(self) -> Self {
+        match self {
+            Self::FramesPerGeneration(frames) if frames > 1 => {
+                Self::FramesPerGeneration(frames / 2)
+            }
+            Self::FramesPerGeneration(_) => Self::GenerationsPerFrame(2),
+            Self::GenerationsPerFrame(generations) => {
+                Self::GenerationsPerFrame((generations * 2).min(MAX_GENERATIONS_PER_FRAME))
+            }
+        }
+    }
+
+    // Halves the speed
+    pub fn slower(self) -> Self {
+        match self {
+            Self::GenerationsPerFrame(generations) if generations > 2 => {
+                Self::GenerationsPerFrame(generations / 2)
+            }
+            Self::GenerationsPerFrame(_) => Self::FramesPerGeneration(1),
+            Self::FramesPerGeneration(frames) => {
+                Self::FramesPerGeneration(frames.saturating_mul(2))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Speed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FramesPerGeneration(frames) => write!(f, "1/{}", frames),
+            Self::GenerationsPerFrame(generations) => write!(f, "{}/1", generations),
+        }
+    }
+}
+
+impl std::str::FromStr for Speed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid speed '{}' (expected e.g. 1/5 or 16/1)", s);
+        let (generations, frames) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let generations: u32 = generations.trim().parse().map_err(|_| invalid())?;
+        let frames: u32 = frames.trim().parse().map_err(|_| invalid())?;
+        match (generations, frames) {
+            (1, frames) if frames > 0 => Ok(Self::FramesPerGeneration(frames)),
+            (generations, 1) if generations > 0 && generations <= MAX_GENERATIONS_PER_FRAME => {
+                Ok(Self::GenerationsPerFrame(generations))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
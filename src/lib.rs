@@ -0,0 +1,9 @@
+pub mod game_of_life;
+pub use game_of_life::*;
+
+pub mod options;
+
+#[cfg(feature = "sdl")]
+pub mod game_of_life_io;
+#[cfg(feature = "sdl")]
+pub mod window;
@@ -1,48 +1,103 @@
 use std::convert::TryInto;
 
-mod game_of_life;
-mod game_of_life_io;
-mod window;
+use game_of_life::{game_of_life_io, options, window};
 
-fn get_window_config() -> Result<window::WindowConfig, Box<dyn std::error::Error>> {
+mod cli;
+
+fn get_window_config(
+    options: &cli::Options,
+    game: &game_of_life::Game,
+    game_config: &game_of_life_io::Config,
+) -> Result<window::WindowConfig, Box<dyn std::error::Error>> {
     Ok(window::WindowConfig {
-        window_name: "Game of life".into(),
+        window_name: game_of_life_io::window_title(game_config),
         size: window::WindowSize {
-            cells_in_width: 100,
-            cells_in_height: 75,
-            cell_width: 8,
-            cell_height: 8,
+            cells_in_width: game.board.width().try_into()?,
+            cells_in_height: game.board.height().try_into()?,
+            cell_width: options.cell_size,
+            cell_height: options.cell_size,
         },
-        update_frame_duration: window::fps_to_duration(60)?,
+        update_frame_duration: window::fps_to_duration(options.fps)?,
         background_color: sdl2::pixels::Color::BLACK,
     })
 }
 
-fn get_game_config() -> game_of_life_io::Config {
+fn get_game_config(options: &cli::Options) -> game_of_life_io::Config {
     game_of_life_io::Config {
-        running: true,
-        ticks_to_update: 5,
+        running: !options.paused,
+        speed: options.speed,
+        resize_policy: options.resize_policy,
+        show_hud: !options.no_hud,
+        palette: options.palette,
+        trail: options.trail,
+        target_generation: None,
+        snapshot_path: options
+            .load
+            .clone()
+            .unwrap_or_else(|| "game_of_life.snapshot".into()),
+        seed: options.game.seed.unwrap_or_else(rand::random),
+        density: options.game.density,
+        symmetry: options.game.symmetry.unwrap_or_default(),
+        soup_size: options.game.soup_size,
+        history_capacity: options.history,
+        history_memory_limit: Some(options.history_memory.saturating_mul(1024 * 1024)),
+        draw_state: 1,
+        message: None,
     }
 }
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let window_config = get_window_config()?;
+    let options = match cli::Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(options::Error::Help) => {
+            print!("{}", cli::usage());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!(
+                "Error: {}\n\nRun with --help to see the available options",
+                e
+            );
+            std::process::exit(2);
+        }
+    };
 
-    let game = game_of_life::Game::create_random(
-        window_config.size.cells_in_width.try_into()?,
-        window_config.size.cells_in_height.try_into()?,
-        game_of_life::rules::ConwayWrapped{}
-    );
+    let mut game_config = get_game_config(&options);
+    let mut game = match &options.load {
+        Some(path) => game_of_life_io::load_snapshot(path, &mut game_config, options.game.threads)?,
+        None => {
+            if options.game.pattern.is_none() {
+                println!("Seed: {}", game_config.seed);
+            }
+            options.game.create_game(game_config.seed)?
+        }
+    };
+    game.set_history(game_config.history());
+    game_config.update_ages(&mut game);
+    let window_config = get_window_config(&options, &game, &game_config)?;
     let game = std::cell::RefCell::new(game);
 
-    let game_config = std::cell::RefCell::new(get_game_config());
+    let game_config = std::cell::RefCell::new(game_config);
     let mut callback_handler = window::CallbackHandler::default();
 
     game_of_life_io::add_input_cb_to_handler(&mut callback_handler, &game, &game_config);
     game_of_life_io::add_step_update_cb_to_handler(&mut callback_handler, &game, &game_config);
 
     callback_handler.add_frame_cb_data(|window_data| {
-        game_of_life_io::display_game(window_data, &game.borrow().board)
+        let game = game.borrow();
+        let game_config = game_config.borrow();
+        window_data.camera.hexagonal = game.rule.hexagonal();
+        game_of_life_io::display_game_with_ages(
+            window_data,
+            &game.board,
+            &*game.rule,
+            game.ages(),
+            game_config.palette,
+            game_config.trail,
+        )
+    });
+    callback_handler.add_frame_cb_data(|window_data| {
+        game_of_life_io::display_hud(window_data, &game.borrow(), &game_config.borrow())
     });
 
     callback_handler.add_event_cb(|event| {
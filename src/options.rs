@@ -0,0 +1,234 @@
+// Command line options shared by the window and the headless binary
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::game_of_life::{board, formats, rules, Game, Symmetry, Topology};
+
+// Options describing the initial game, the binaries add their own options
+// to the lines above and below
+pub const USAGE: &str = "\
+    -W, --width <CELLS>         Board width in cells [default: 100]
+    -H, --height <CELLS>        Board height in cells [default: 75]
+    -r, --rule <RULE>           Rule string, e.g. B3/S23, 23/3 or 345/2/4, or a Golly
+                                .rule file or the name of one in the rules
+                                directories, e.g. WireWorld [default: B3/S23]
+    -t, --topology <NAME>       plane, torus, horizontal-cylinder, vertical-cylinder,
+                                klein-bottle or cross-surface [default: torus]
+    -p, --pattern <FILE>        Pattern file (.rle, .cells, .lif) placed in the centre
+                                of an empty board
+        --seed <NUMBER>         Seed of the random board [default: random]
+        --density <RATIO>       Ratio of live cells of the random board, 0-1 [default: 0.5]
+        --symmetry <CLASS>      Start with a symmetric soup in the centre instead of
+                                random board: C1, C2_1, C2_2, C2_4, C4_1, C4_4, D2_+1,
+                                D2_+2, D2_x, D4_+1, D4_+2, D4_+4, D4_x1, D4_x4, D8_1
+                                or D8_4 [default: C1]
+        --soup-size <CELLS>     Side of the soup [default: 16]
+        --threads <COUNT>       Threads used to compute generations [default: 1]
+";
+
+#[derive(Debug)]
+pub enum Error {
+    Help,
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Help => write!(f, "Help requested"),
+            Self::UnknownOption(option) => write!(f, "Unknown option '{}'", option),
+            Self::MissingValue(option) => write!(f, "Option '{}' requires a value", option),
+            Self::InvalidValue {
+                option,
+                value,
+                reason,
+            } => write!(f, "Invalid value '{}' for '{}': {}", value, option, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn parse_value<T>(
+    option: &str,
+    value: String,
+    check: impl Fn(&T) -> Result<(), String>,
+) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let invalid = |reason: String| Error::InvalidValue {
+        option: option.into(),
+        value: value.clone(),
+        reason,
+    };
+    let parsed = value.parse::<T>().map_err(|e| invalid(e.to_string()))?;
+    check(&parsed).map_err(invalid)?;
+    Ok(parsed)
+}
+
+pub fn positive<T: PartialOrd + Default>(value: &T) -> Result<(), String> {
+    if *value > T::default() {
+        Ok(())
+    } else {
+        Err("has to be greater than 0".into())
+    }
+}
+
+pub fn board_size(value: &usize) -> Result<(), String> {
+    if (1..=board::MAX_SIZE).contains(value) {
+        Ok(())
+    } else {
+        Err(format!("has to be between 1 and {}", board::MAX_SIZE))
+    }
+}
+
+pub fn any<T>(_: &T) -> Result<(), String> {
+    Ok(())
+}
+
+// Calls `f` with every option and a function taking its value, both
+// `--option value` and `--option=value` are accepted
+pub fn parse_args<F>(args: impl IntoIterator<Item = String>, mut f: F) -> Result<(), Error>
+where
+    F: FnMut(&str, &mut dyn FnMut() -> Result<String, Error>) -> Result<(), Error>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => {
+                (option.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| Error::MissingValue(option.clone()))
+        };
+        f(&option, &mut value)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct GameOptions {
+    pub width: usize,
+    pub height: usize,
+    pub rule: Option<String>,
+    pub topology: Topology,
+    pub pattern: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub density: f64,
+    pub symmetry: Option<Symmetry>,
+    pub soup_size: usize,
+    pub threads: usize,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            width: 100,
+            height: 75,
+            rule: None,
+            topology: Topology::Torus,
+            pattern: None,
+            seed: None,
+            density: 0.5,
+            symmetry: None,
+            soup_size: 16,
+            threads: 1,
+        }
+    }
+}
+
+impl GameOptions {
+    // Parses one of the options listed in `USAGE`
+    pub fn parse_option(
+        &mut self,
+        option: &str,
+        value: &mut dyn FnMut() -> Result<String, Error>,
+    ) -> Result<(), Error> {
+        match option {
+            "-h" | "--help" => return Err(Error::Help),
+            "-W" | "--width" => self.width = parse_value(option, value()?, board_size)?,
+            "-H" | "--height" => self.height = parse_value(option, value()?, board_size)?,
+            "-r" | "--rule" => {
+                let rule = value()?;
+                if let Err(e) = rules::parse(&rule) {
+                    return Err(Error::InvalidValue {
+                        option: option.into(),
+                        value: rule,
+                        reason: e.to_string(),
+                    });
+                }
+                self.rule = Some(rule);
+            }
+            "-t" | "--topology" => self.topology = parse_value(option, value()?, any)?,
+            "-p" | "--pattern" => self.pattern = Some(value()?.into()),
+            "--seed" => self.seed = Some(parse_value(option, value()?, any)?),
+            "--density" => {
+                self.density = parse_value(option, value()?, |density: &f64| {
+                    if (0.0..=1.0).contains(density) {
+                        Ok(())
+                    } else {
+                        Err("has to be between 0 and 1".into())
+                    }
+                })?
+            }
+            "--symmetry" => self.symmetry = Some(parse_value(option, value()?, any)?),
+            "--soup-size" => self.soup_size = parse_value(option, value()?, positive)?,
+            "--threads" => self.threads = parse_value(option, value()?, positive)?,
+            _ => return Err(Error::UnknownOption(option.into())),
+        }
+        Ok(())
+    }
+
+    // Board with the pattern in the centre, or filled with random cells from
+    // `seed` when there is no pattern
+    pub fn create_game(&self, seed: u64) -> Result<Game<'static>, Box<dyn std::error::Error>> {
+        let mut pattern = match &self.pattern {
+            Some(path) => Some(formats::read_file(path)?),
+            None => None,
+        };
+        let rule = match (&self.rule, pattern.as_mut().and_then(|p| p.rule.take())) {
+            (Some(rule), _) => rules::parse(rule)?,
+            (None, Some(rule)) => rule,
+            (None, None) => Box::new(rules::Conway {}),
+        };
+        let mut game = Game::create_boxed(self.width, self.height, rule)
+            .with_topology(self.topology)
+            .with_threads(self.threads);
+
+        match pattern {
+            Some(pattern) => {
+                let centre = (self.width as i32 / 2, self.height as i32 / 2);
+                if pattern.add(&mut game.board, centre).is_none() {
+                    eprintln!("Pattern does not fit the board, it was cut off");
+                }
+            }
+            None => match self.symmetry {
+                Some(symmetry) => {
+                    let (width, height) = symmetry.dimensions(self.soup_size);
+                    let pos = (
+                        (self.width as i32 - width as i32) / 2,
+                        (self.height as i32 - height as i32) / 2,
+                    );
+                    game.add_soup(seed, self.density, symmetry, pos, self.soup_size)
+                        .ok_or("Soup does not fit the board")?;
+                }
+                None => game.randomize_seeded(seed, self.density),
+            },
+        }
+        Ok(game)
+    }
+}
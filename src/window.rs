@@ -1,7 +1,10 @@
 pub mod callback_handler;
+pub mod camera;
 pub mod size;
+pub mod text;
 
 pub use callback_handler::CallbackHandler;
+pub use camera::Camera;
 pub use size::WindowSize;
 
 pub trait IntoWindowError: Into<Box<dyn std::error::Error>> {}
@@ -80,6 +83,20 @@ pub struct WindowData {
     pub window_config: WindowConfig,
     pub canvas: sdl2::render::Canvas<sdl2::video::Window>,
     pub mouse_pos: (i32, i32),
+    pub camera: Camera,
+    // Measured number of frames per second
+    pub fps: f64,
+}
+
+impl WindowData {
+    // Current size of the window in pixels, which may differ from
+    // `window_config.size` once the window is resized
+    pub fn output_size(&self) -> (u32, u32) {
+        let size = &self.window_config.size;
+        self.canvas
+            .output_size()
+            .unwrap_or_else(|_| (size.get_width(), size.get_height()))
+    }
 }
 
 fn build(window_config: WindowConfig) -> Result<(WindowData, sdl2::EventPump)> {
@@ -93,6 +110,7 @@ fn build(window_config: WindowConfig) -> Result<(WindowData, sdl2::EventPump)> {
             window_config.size.get_height(),
         )
         .position_centered()
+        .resizable()
         .build()?;
     let canvas = window.into_canvas().build()?;
     let event_pump = sdl_context.event_pump()?;
@@ -100,12 +118,18 @@ fn build(window_config: WindowConfig) -> Result<(WindowData, sdl2::EventPump)> {
         window_config,
         canvas,
         mouse_pos: (0, 0),
+        camera: Camera::default(),
+        fps: 0.0,
     };
     Ok((window_data, event_pump))
 }
 
 pub fn run(window_config: WindowConfig, mut handler: CallbackHandler<WindowData>) -> Result<()> {
     let (mut window_data, mut event_pump) = build(window_config)?;
+    // Frame rate is measured over intervals of this length
+    let fps_interval = std::time::Duration::from_millis(500);
+    let mut fps_frames = 0;
+    let mut fps_start = std::time::Instant::now();
 
     loop {
         let next_cycle_time =
@@ -131,5 +155,13 @@ pub fn run(window_config: WindowConfig, mut handler: CallbackHandler<WindowData>
 
         // Sleep for the rest of the cycle
         sleep_until(next_cycle_time);
+
+        fps_frames += 1;
+        let elapsed = fps_start.elapsed();
+        if elapsed >= fps_interval {
+            window_data.fps = f64::from(fps_frames) / elapsed.as_secs_f64();
+            fps_frames = 0;
+            fps_start = std::time::Instant::now();
+        }
     }
 }
@@ -0,0 +1,169 @@
+use super::WindowSize;
+
+const MIN_ZOOM: f64 = 1.0 / 64.0;
+const MAX_ZOOM: f64 = 64.0;
+
+// Part of the board shown in the window, `offset` is the position (in cells)
+// shown in the top left corner, `zoom` scales the cell size of `WindowSize`
+//
+// On hexagonal grids every row is shifted by half a cell to the left of
+// the row above, positions of cells are converted to positions in the
+// view, in which `offset` is given, by `cell_to_view`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub offset: (f64, f64),
+    pub zoom: f64,
+    pub hexagonal: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            offset: (0.0, 0.0),
+            zoom: 1.0,
+            hexagonal: false,
+        }
+    }
+}
+
+impl Camera {
+    // Shifts the rows on hexagonal grids so that centre of the cell (0, 0)
+    // stays in place
+    pub fn cell_to_view(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        if self.hexagonal {
+            (x - (y - 0.5) / 2.0, y)
+        } else {
+            (x, y)
+        }
+    }
+
+    pub fn view_to_cell(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        if self.hexagonal {
+            (x + (y - 0.5) / 2.0, y)
+        } else {
+            (x, y)
+        }
+    }
+
+    // Bounding box in the view of the rectangle [from, to) of cells
+    fn cells_to_view(&self, from: (f64, f64), to: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+        let corners = [
+            self.cell_to_view(from),
+            self.cell_to_view((to.0, from.1)),
+            self.cell_to_view((from.0, to.1)),
+            self.cell_to_view(to),
+        ];
+        let xs = corners.iter().map(|corner| corner.0);
+        let from_x = xs.clone().fold(f64::INFINITY, f64::min);
+        let to_x = xs.fold(f64::NEG_INFINITY, f64::max);
+        ((from_x, from.1), (to_x, to.1))
+    }
+
+    // Size of a cell on the screen in pixels
+    pub fn cell_size(&self, size: &WindowSize) -> (f64, f64) {
+        (
+            f64::from(size.cell_width) * self.zoom,
+            f64::from(size.cell_height) * self.zoom,
+        )
+    }
+
+    pub fn window_to_view(&self, size: &WindowSize, (x, y): (i32, i32)) -> (f64, f64) {
+        let (cell_width, cell_height) = self.cell_size(size);
+        (
+            self.offset.0 + f64::from(x) / cell_width,
+            self.offset.1 + f64::from(y) / cell_height,
+        )
+    }
+
+    pub fn view_to_window(&self, size: &WindowSize, (x, y): (f64, f64)) -> (f64, f64) {
+        let (cell_width, cell_height) = self.cell_size(size);
+        (
+            (x - self.offset.0) * cell_width,
+            (y - self.offset.1) * cell_height,
+        )
+    }
+
+    pub fn window_to_cell(&self, size: &WindowSize, pos: (i32, i32)) -> (f64, f64) {
+        self.view_to_cell(self.window_to_view(size, pos))
+    }
+
+    pub fn cell_to_window(&self, size: &WindowSize, pos: (f64, f64)) -> (f64, f64) {
+        self.view_to_window(size, self.cell_to_view(pos))
+    }
+
+    // Cell under the window position, on hexagonal grids the cell with the
+    // nearest centre, which makes the cells regular hexagons
+    pub fn cell_at(&self, size: &WindowSize, pos: (i32, i32)) -> (i32, i32) {
+        let (x, y) = self.window_to_cell(size, pos);
+        if !self.hexagonal {
+            return (x.floor() as i32, y.floor() as i32);
+        }
+        let view = self.window_to_view(size, pos);
+        // Rows are 3/4 of the height of the hexagons apart
+        let row_height = 3.0_f64.sqrt() / 2.0;
+        let distance = |cell: (i32, i32)| {
+            let centre = self.cell_to_view((f64::from(cell.0) + 0.5, f64::from(cell.1) + 0.5));
+            (view.0 - centre.0).powi(2) + ((view.1 - centre.1) * row_height).powi(2)
+        };
+        let row = y.floor() as i32;
+        (row - 1..=row + 1)
+            .flat_map(|row| {
+                let column = self.view_to_cell((view.0, f64::from(row) + 0.5)).0;
+                let column = column.floor() as i32;
+                vec![(column, row), (column - 1, row), (column + 1, row)]
+            })
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .expect("Internal logic error")
+    }
+
+    // Moves the view by the given number of pixels
+    pub fn pan(&mut self, size: &WindowSize, (x, y): (i32, i32)) {
+        let (cell_width, cell_height) = self.cell_size(size);
+        self.offset.0 += f64::from(x) / cell_width;
+        self.offset.1 += f64::from(y) / cell_height;
+    }
+
+    // Multiplies zoom by `factor`, keeping the cell under `window_pos` in place
+    pub fn zoom_at(&mut self, size: &WindowSize, window_pos: (i32, i32), factor: f64) {
+        let anchor = self.window_to_view(size, window_pos);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let (cell_width, cell_height) = self.cell_size(size);
+        self.offset = (
+            anchor.0 - f64::from(window_pos.0) / cell_width,
+            anchor.1 - f64::from(window_pos.1) / cell_height,
+        );
+    }
+
+    // Shows the rectangle [from, to) of cells as large as possible, centred
+    // in the window of `window` pixels
+    pub fn fit(&mut self, size: &WindowSize, window: (u32, u32), from: (f64, f64), to: (f64, f64)) {
+        let (from, to) = self.cells_to_view(from, to);
+        let cells = ((to.0 - from.0).max(1.0), (to.1 - from.1).max(1.0));
+        let window = (f64::from(window.0), f64::from(window.1));
+        let zoom_x = window.0 / (cells.0 * f64::from(size.cell_width));
+        let zoom_y = window.1 / (cells.1 * f64::from(size.cell_height));
+        self.zoom = zoom_x.min(zoom_y).clamp(MIN_ZOOM, MAX_ZOOM);
+        let (cell_width, cell_height) = self.cell_size(size);
+        self.offset = (
+            from.0 + (cells.0 - window.0 / cell_width) / 2.0,
+            from.1 + (cells.1 - window.1 / cell_height) / 2.0,
+        );
+    }
+
+    // Keeps the zoom and moves the view so that the rectangle [from, to)
+    // of cells is in the centre of the window of `window` pixels
+    pub fn center(
+        &mut self,
+        size: &WindowSize,
+        window: (u32, u32),
+        from: (f64, f64),
+        to: (f64, f64),
+    ) {
+        let (from, to) = self.cells_to_view(from, to);
+        let (cell_width, cell_height) = self.cell_size(size);
+        self.offset = (
+            (from.0 + to.0 - f64::from(window.0) / cell_width) / 2.0,
+            (from.1 + to.1 - f64::from(window.1) / cell_height) / 2.0,
+        );
+    }
+}